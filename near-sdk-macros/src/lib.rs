@@ -790,3 +790,82 @@ pub fn derive_event_attributes(item: TokenStream) -> TokenStream {
         )
     }
 }
+
+#[proc_macro_derive(RandomVariant, attributes(weight))]
+pub fn derive_random_variant(item: TokenStream) -> TokenStream {
+    let input = match syn::parse::<ItemEnum>(item) {
+        Ok(input) => input,
+        Err(_) => {
+            return TokenStream::from(
+                syn::Error::new(
+                    Span::call_site(),
+                    "RandomVariant can only be used as a derive on enums.",
+                )
+                .to_compile_error(),
+            );
+        }
+    };
+
+    let mut arms = Vec::with_capacity(input.variants.len());
+    let mut total: u64 = 0;
+    for variant in &input.variants {
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return TokenStream::from(
+                syn::Error::new_spanned(
+                    variant,
+                    "RandomVariant can only be derived for enums with unit variants.",
+                )
+                .to_compile_error(),
+            );
+        }
+        let mut weight: u64 = 1;
+        for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("weight")) {
+            match attr.parse_args::<syn::LitInt>().and_then(|lit| lit.base10_parse::<u64>()) {
+                Ok(value) => weight = value,
+                Err(e) => return TokenStream::from(e.to_compile_error()),
+            }
+        }
+        if weight == 0 {
+            continue;
+        }
+        let start = proc_macro2::Literal::u64_suffixed(total);
+        total = match total.checked_add(weight) {
+            Some(total) => total,
+            None => {
+                return TokenStream::from(
+                    syn::Error::new_spanned(variant, "RandomVariant weights overflow u64.")
+                        .to_compile_error(),
+                );
+            }
+        };
+        let end = proc_macro2::Literal::u64_suffixed(total - 1);
+        let ident = &variant.ident;
+        arms.push(quote! { #start..=#end => Self::#ident, });
+    }
+    if total == 0 {
+        return TokenStream::from(
+            syn::Error::new(
+                Span::call_site(),
+                "RandomVariant requires at least one variant with a positive weight.",
+            )
+            .to_compile_error(),
+        );
+    }
+    let total = proc_macro2::Literal::u64_suffixed(total);
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    TokenStream::from(quote! {
+        #[automatically_derived]
+        impl #impl_generics #name #type_generics #where_clause {
+            /// Returns a variant chosen at random, with probability proportional to its
+            /// `#[weight(n)]` (one by default).
+            pub fn random_variant(rng: &mut ::near_sdk::random::SecureRng) -> Self {
+                match ::near_sdk::random::Rng::u64(rng, 0..#total) {
+                    #(#arms)*
+                    _ => ::std::unreachable!(),
+                }
+            }
+        }
+    })
+}
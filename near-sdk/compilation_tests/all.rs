@@ -42,5 +42,7 @@ fn compilation_tests() {
     t.compile_fail("compilation_tests/contract_metadata_fn_name.rs");
     t.pass("compilation_tests/contract_metadata_bindgen.rs");
     t.pass("compilation_tests/types.rs");
+    t.pass("compilation_tests/random_variant.rs");
+    t.pass("compilation_tests/random_state.rs");
     t.compile_fail("compilation_tests/nested_near_error.rs");
 }
@@ -0,0 +1,25 @@
+//! Testing a SecureRng kept in contract state.
+
+use near_sdk::random::{Rng, SecureRng};
+use near_sdk::{AccountId, PanicOnDefault, near};
+
+#[derive(PanicOnDefault)]
+#[near(contract_state)]
+struct Raffle {
+    participants: Vec<AccountId>,
+    rng: SecureRng,
+}
+
+#[near]
+impl Raffle {
+    #[init]
+    pub fn new(participants: Vec<AccountId>) -> Self {
+        Self { participants, rng: SecureRng::new() }
+    }
+
+    pub fn draw(&mut self) -> Option<AccountId> {
+        self.rng.choice(&self.participants).cloned()
+    }
+}
+
+fn main() {}
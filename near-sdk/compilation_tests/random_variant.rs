@@ -0,0 +1,36 @@
+//! Testing RandomVariant macro.
+
+use near_sdk::near;
+use near_sdk::random::{RandomVariant, SecureRng};
+
+#[derive(RandomVariant)]
+enum Direction {
+    North,
+    East,
+    South,
+}
+
+#[derive(RandomVariant)]
+enum Prize {
+    #[weight(90)]
+    Common,
+    #[weight(10)]
+    Rare,
+    #[weight(0)]
+    Unobtainable,
+}
+
+#[derive(Default)]
+#[near(contract_state)]
+struct Contract {}
+
+#[near]
+impl Contract {
+    pub fn spin(&self) -> bool {
+        let mut rng = SecureRng::new();
+        let _ = Direction::random_variant(&mut rng);
+        matches!(Prize::random_variant(&mut rng), Prize::Rare)
+    }
+}
+
+fn main() {}
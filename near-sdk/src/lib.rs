@@ -1618,6 +1618,9 @@ pub use crate::events::{AsNep297Event, Nep297Event};
 
 pub mod state;
 
+#[cfg(feature = "secure-random")]
+pub mod random;
+
 #[cfg(feature = "deterministic-account-ids")]
 pub mod state_init;
 
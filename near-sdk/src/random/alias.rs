@@ -0,0 +1,141 @@
+use near_sdk_macros::near;
+
+use super::{Distribution, Rng, SecureRng};
+use crate::env;
+
+/// Weighted index sampler using Vose's alias method: building it takes `O(n)`, after which
+/// every [`sample`](Self::sample) takes `O(1)` regardless of the number of weights.
+///
+/// The tables are computed with integer arithmetic, so indices are drawn with probabilities
+/// exactly proportional to their weights and zero weights are never drawn. The sampler is
+/// Borsh-serializable, so a fixed weight table can be built once and kept in contract state.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::random::{SecureRng, WeightedSampler};
+///
+/// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+/// let banner = WeightedSampler::new(&[90, 9, 1]);
+/// let mut rng = SecureRng::new();
+/// let pulls: Vec<usize> = (0..10).map(|_| banner.sample(&mut rng)).collect();
+/// assert!(pulls.iter().all(|&pull| pull < 3));
+/// ```
+#[near(inside_nearsdk)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedSampler {
+    /// Sum of the weights, which every threshold is expressed against.
+    total: u128,
+    /// Draws below `threshold[i]` out of `total` keep column `i`, the others take its alias.
+    threshold: Vec<u128>,
+    alias: Vec<u64>,
+}
+
+impl WeightedSampler {
+    /// Builds the alias tables for `weights`.
+    ///
+    /// Panics if no weight is positive.
+    pub fn new(weights: &[u64]) -> Self {
+        let total: u128 = weights.iter().map(|&weight| u128::from(weight)).sum();
+        if total == 0 {
+            env::panic_str("WeightedSampler requires at least one positive weight");
+        }
+        // Weights scaled by the number of columns, so that a full column holds `total`.
+        let mut scaled: Vec<u128> =
+            weights.iter().map(|&weight| u128::from(weight) * weights.len() as u128).collect();
+        let mut threshold = vec![total; weights.len()];
+        let mut alias: Vec<u64> = (0..weights.len() as u64).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..weights.len()).partition(|&i| scaled[i] < total);
+
+        while let (Some(&less), Some(&more)) = (small.last(), large.last()) {
+            small.pop();
+            threshold[less] = scaled[less];
+            alias[less] = more as u64;
+            scaled[more] -= total - scaled[less];
+            if scaled[more] < total {
+                large.pop();
+                small.push(more);
+            }
+        }
+        // Exact arithmetic leaves every remaining column full, which `threshold` already is.
+        Self { total, threshold, alias }
+    }
+
+    /// Returns the number of weights the sampler was built with.
+    pub fn len(&self) -> usize {
+        self.threshold.len()
+    }
+
+    /// Returns `true` if the sampler was built without weights, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.threshold.is_empty()
+    }
+
+    /// Draws an index with probability proportional to its weight.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let column = rng.usize(0..self.threshold.len());
+        if rng.u128(0..self.total) < self.threshold[column] {
+            column
+        } else {
+            self.alias[column] as usize
+        }
+    }
+}
+
+impl Distribution<usize> for WeightedSampler {
+    fn sample(&self, rng: &mut SecureRng) -> usize {
+        WeightedSampler::sample(self, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::test_rng;
+
+    #[test]
+    fn frequencies_match_weights() {
+        let weights = [50, 0, 25, 15, 10, 0];
+        let sampler = WeightedSampler::new(&weights);
+        assert_eq!(sampler.len(), 6);
+        let mut rng = test_rng(1);
+        let mut counts = [0u32; 6];
+        for _ in 0..50_000 {
+            counts[sampler.sample(&mut rng)] += 1;
+        }
+        assert_eq!((counts[1], counts[5]), (0, 0));
+        for (count, weight) in counts.iter().zip(weights) {
+            let expected = 500.0 * weight as f64;
+            assert!((*count as f64 - expected).abs() < 250.0, "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn tables_are_exact() {
+        // A column's threshold plus what other columns alias to it adds up to its weight.
+        let weights = [7, 1, 0, 3, 9, 4];
+        let sampler = WeightedSampler::new(&weights);
+        let n = weights.len() as u128;
+        let mut mass = vec![0u128; weights.len()];
+        for (column, &threshold) in sampler.threshold.iter().enumerate() {
+            mass[column] += threshold;
+            mass[sampler.alias[column] as usize] += sampler.total - threshold;
+        }
+        let expected: Vec<u128> = weights.iter().map(|&weight| u128::from(weight) * n).collect();
+        assert_eq!(mass, expected);
+    }
+
+    #[test]
+    fn single_weight() {
+        let sampler = WeightedSampler::new(&[3]);
+        let mut rng = test_rng(2);
+        assert!((0..100).all(|_| sampler.sample(&mut rng) == 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one positive weight")]
+    fn zero_weights_rejected() {
+        WeightedSampler::new(&[0, 0]);
+    }
+}
@@ -0,0 +1,265 @@
+use super::{Rng, SecureRng};
+use crate::env;
+
+impl SecureRng {
+    /// Splits `total` into `periods` amounts that vary by up to `jitter_pct` percent (capped at
+    /// 100) around an even split, and always sum to exactly `total`.
+    ///
+    /// Every amount lies within the band. The deviations from the even split sum to zero, so
+    /// they do not accumulate, and only the rounding remainder of `total / periods` is added
+    /// to the last period on top of its draw.
+    ///
+    /// Returns an empty vector if `periods` is zero.
+    pub fn random_vesting(&mut self, total: u128, periods: usize, jitter_pct: u8) -> Vec<u128> {
+        if periods == 0 {
+            return Vec::new();
+        }
+        let base = total / periods as u128;
+        let jitter = u128::from(jitter_pct.min(100));
+        // Computed in two parts so that `base * jitter` cannot overflow.
+        let spread = base / 100 * jitter + base % 100 * jitter / 100;
+
+        // Each offset from `base - spread` lies in `0..=2 * spread` and the offsets sum to
+        // `periods * spread`. Every draw is narrowed so the later ones can still reach that
+        // sum, then the offsets are shuffled so no period is favored by its position.
+        let width = spread.saturating_mul(2);
+        let mut remaining = spread * periods as u128;
+        let mut offsets = Vec::with_capacity(periods);
+        for drawn in 1..periods {
+            let after = (periods - drawn) as u128;
+            let low = remaining.saturating_sub(after.saturating_mul(width));
+            let offset = self.u128(low..=width.min(remaining));
+            remaining -= offset;
+            offsets.push(offset);
+        }
+        offsets.push(remaining);
+        self.shuffle(&mut offsets);
+
+        let mut amounts: Vec<u128> =
+            offsets.into_iter().map(|offset| base - spread + offset).collect();
+        amounts[periods - 1] += total % periods as u128;
+        amounts
+    }
+
+    /// Randomly allocates exactly `budget` points across `categories`, giving each category
+    /// between `min_each` and `max_each` points inclusive.
+    ///
+    /// Returns `None` if no allocation satisfies the constraints.
+    pub fn allocate_points(
+        &mut self,
+        budget: u32,
+        categories: usize,
+        min_each: u32,
+        max_each: u32,
+    ) -> Option<Vec<u32>> {
+        let (count, budget) = (categories as u64, u64::from(budget));
+        let (min_each, max_each) = (u64::from(min_each), u64::from(max_each));
+        if min_each > max_each
+            || budget < count.saturating_mul(min_each)
+            || budget > count.saturating_mul(max_each)
+        {
+            return None;
+        }
+
+        // Categories are filled in random order so that none is favored by its position.
+        let mut order: Vec<usize> = (0..categories).collect();
+        self.shuffle(&mut order);
+        let mut allocation = vec![0; categories];
+        let mut remaining = budget;
+        for (filled, &category) in order.iter().enumerate() {
+            let after = count - filled as u64 - 1;
+            let low = min_each.max(remaining.saturating_sub(after.saturating_mul(max_each)));
+            let high = max_each.min(remaining - after * min_each);
+            let points = self.u64(low..=high);
+            remaining -= points;
+            allocation[category] = points as u32;
+        }
+        Some(allocation)
+    }
+
+    /// Visits `items` in random order and collects them while their total weight fits within
+    /// `capacity`, stopping at the first item that would not fit.
+    pub fn random_fill<T: Clone>(&mut self, items: &[(T, u64)], capacity: u64) -> Vec<T> {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        self.shuffle(&mut order);
+        let mut filled = Vec::new();
+        let mut used = 0u64;
+        for index in order {
+            let (item, weight) = &items[index];
+            match used.checked_add(*weight) {
+                Some(total) if total <= capacity => used = total,
+                _ => break,
+            }
+            filled.push(item.clone());
+        }
+        filled
+    }
+
+    /// Shuffles `items` and splits them into `k` folds whose sizes differ by at most one, with
+    /// the larger folds first. Every item lands in exactly one fold.
+    ///
+    /// Returns no folds if `k` is zero.
+    pub fn k_fold<T: Clone>(&mut self, items: &[T], k: usize) -> Vec<Vec<T>> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut shuffled = items.to_vec();
+        self.shuffle(&mut shuffled);
+        let (size, larger) = (items.len() / k, items.len() % k);
+        let mut rest = shuffled.into_iter();
+        (0..k).map(|fold| rest.by_ref().take(size + usize::from(fold < larger)).collect()).collect()
+    }
+
+    /// Pairs every label with a random color from `palette`.
+    ///
+    /// Colors are distinct as long as the palette has at least as many colors as there are
+    /// labels. Otherwise the shuffled palette is reused from the start as many times as needed,
+    /// so every color is used either `labels.len() / palette.len()` times or once more.
+    ///
+    /// Panics if `palette` is empty and `labels` is not.
+    pub fn assign_palette<L: Clone>(
+        &mut self,
+        labels: &[L],
+        palette: &[String],
+    ) -> Vec<(L, String)> {
+        if palette.is_empty() && !labels.is_empty() {
+            env::panic_str("assign_palette: the palette is empty");
+        }
+        let mut colors: Vec<&String> = palette.iter().collect();
+        self.shuffle_partial(&mut colors, labels.len());
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| (label.clone(), colors[i % colors.len()].clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::test_rng;
+
+    #[test]
+    fn vesting_sums_to_total_within_band() {
+        let total = 1_000_000_000_000_000_000_000_000u128;
+        let amounts = test_rng(1).random_vesting(total, 12, 20);
+        assert_eq!(amounts.len(), 12);
+        assert_eq!(amounts.iter().sum::<u128>(), total);
+        let (base, remainder) = (total / 12, total % 12);
+        for amount in &amounts[..11] {
+            assert!(*amount >= base - base / 5 && *amount <= base + base / 5);
+        }
+        assert!(amounts[11] >= base - base / 5 && amounts[11] <= base + base / 5 + remainder);
+        assert_ne!(amounts, test_rng(2).random_vesting(total, 12, 20));
+    }
+
+    #[test]
+    fn long_vesting_keeps_every_period_in_band() {
+        let mut rng = test_rng(3);
+        for (total, periods, jitter_pct) in
+            [(1_000_000u128, 1_000, 10), (u128::MAX, 48, 100), (999, 7, 50), (5, 10, 30)]
+        {
+            let amounts = rng.random_vesting(total, periods, jitter_pct);
+            assert_eq!(amounts.iter().sum::<u128>(), total);
+            let base = total / periods as u128;
+            let spread =
+                base / 100 * u128::from(jitter_pct) + base % 100 * u128::from(jitter_pct) / 100;
+            let remainder = total % periods as u128;
+            for (period, &amount) in amounts.iter().enumerate() {
+                let extra = if period == periods - 1 { remainder } else { 0 };
+                assert!(amount >= base - spread, "{period}: {amount}");
+                assert!(amount - extra <= base + spread, "{period}: {amount}");
+            }
+        }
+        let wide = rng.random_vesting(1_000_000, 100, 100);
+        assert!(
+            wide.iter().any(|&amount| amount > 15_000) && wide.iter().any(|&amount| amount < 5_000)
+        );
+        assert_eq!(rng.random_vesting(7, 1, 50), [7]);
+        assert!(rng.random_vesting(7, 0, 50).is_empty());
+        assert_eq!(rng.random_vesting(100, 3, 0), [33, 33, 34]);
+    }
+
+    #[test]
+    fn allocation_respects_constraints() {
+        let mut rng = test_rng(4);
+        let mut distinct = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let allocation = rng.allocate_points(30, 5, 2, 10).unwrap();
+            assert_eq!(allocation.len(), 5);
+            assert_eq!(allocation.iter().sum::<u32>(), 30);
+            assert!(allocation.iter().all(|points| (2..=10).contains(points)));
+            distinct.insert(allocation);
+        }
+        assert!(distinct.len() > 10);
+        assert_eq!(rng.allocate_points(10, 2, 5, 5), Some(vec![5, 5]));
+        assert_eq!(rng.allocate_points(0, 0, 1, 2), Some(vec![]));
+    }
+
+    #[test]
+    fn infeasible_allocation() {
+        let mut rng = test_rng(5);
+        assert_eq!(rng.allocate_points(9, 5, 2, 10), None);
+        assert_eq!(rng.allocate_points(51, 5, 2, 10), None);
+        assert_eq!(rng.allocate_points(10, 2, 6, 4), None);
+        assert_eq!(rng.allocate_points(1, 0, 0, 1), None);
+    }
+
+    #[test]
+    fn random_fill_stays_within_capacity() {
+        let items: Vec<(char, u64)> = ('a'..='j').zip([3, 7, 2, 9, 4, 1, 6, 8, 5, 2]).collect();
+        let weight_of = |picked: &[char]| -> u64 {
+            picked.iter().map(|c| items.iter().find(|(item, _)| item == c).unwrap().1).sum()
+        };
+        let mut rng = test_rng(6);
+        let mut distinct = std::collections::HashSet::new();
+        for _ in 0..50 {
+            let picked = rng.random_fill(&items, 20);
+            assert!(weight_of(&picked) <= 20);
+            distinct.insert(picked);
+        }
+        assert!(distinct.len() > 10);
+        assert_ne!(test_rng(7).random_fill(&items, 20), test_rng(8).random_fill(&items, 20));
+        assert_eq!(rng.random_fill(&items, 100).len(), items.len());
+        assert_eq!(rng.random_fill(&[("a", u64::MAX), ("b", u64::MAX)], u64::MAX).len(), 1);
+    }
+
+    #[test]
+    fn k_folds_partition_items() {
+        let mut rng = test_rng(9);
+        let items: Vec<u32> = (0..23).collect();
+        let sizes = |folds: &[Vec<u32>]| folds.iter().map(Vec::len).collect::<Vec<_>>();
+        let folds = rng.k_fold(&items, 5);
+        assert_eq!(sizes(&folds), [5, 5, 5, 4, 4]);
+        let mut all = folds.concat();
+        assert_ne!(all, items);
+        all.sort_unstable();
+        assert_eq!(all, items);
+
+        assert_eq!(sizes(&rng.k_fold(&items[..2], 4)), [1, 1, 0, 0]);
+        assert!(rng.k_fold(&items, 0).is_empty());
+    }
+
+    #[test]
+    fn palette_colors_are_distinct_when_possible() {
+        let palette: Vec<String> =
+            ["red", "green", "blue", "cyan", "magenta"].map(String::from).to_vec();
+        let labels = ["a", "b", "c", "d"];
+        let assigned = test_rng(10).assign_palette(&labels, &palette);
+        assert_eq!(assigned, test_rng(10).assign_palette(&labels, &palette));
+        assert_eq!(assigned.iter().map(|(label, _)| *label).collect::<Vec<_>>(), labels);
+        let mut colors: Vec<&String> = assigned.iter().map(|(_, color)| color).collect();
+        colors.sort_unstable();
+        colors.dedup();
+        assert_eq!(colors.len(), 4);
+        assert!(colors.iter().all(|color| palette.contains(color)));
+
+        let many: Vec<u32> = (0..12).collect();
+        let assigned = test_rng(11).assign_palette(&many, &palette);
+        for color in &palette {
+            let uses = assigned.iter().filter(|(_, assigned)| assigned == color).count();
+            assert!(uses == 2 || uses == 3, "{color} used {uses} times");
+        }
+        assert!(test_rng(12).assign_palette::<u8>(&[], &[]).is_empty());
+    }
+}
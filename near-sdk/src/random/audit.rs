@@ -0,0 +1,214 @@
+use near_sdk_macros::near;
+
+use std::ops::Range;
+
+use super::{Rng, SecureRng};
+use crate::json_types::{Base58CryptoHash, U128};
+use crate::{BlockHeight, env};
+
+/// Snapshot of a generator's position in its stream, meant to be logged before and after
+/// draws that need to be audited.
+///
+/// `seed` and `word_pos` are enough to replay the following draws with
+/// [`SecureRng::replay`], whichever entropy and domain tags the generator was derived from.
+/// The domain tag is not recorded separately because it is already folded into `seed`.
+#[near(inside_nearsdk, serializers = [json, borsh])]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// Height of the block in which the record was taken.
+    pub block_height: BlockHeight,
+    /// Block random seed at the time of the record, for reference. Generators mix it with
+    /// other inputs, so it cannot be used to replay draws on its own.
+    pub random_seed: Base58CryptoHash,
+    /// Seed of the generator, as returned by [`SecureRng::seed`].
+    pub seed: Base58CryptoHash,
+    /// Number of 32-bit words already consumed from the stream.
+    pub word_pos: U128,
+}
+
+/// A kind of draw whose consumption of the stream can be estimated with
+/// [`SecureRng::estimate_bits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngOp {
+    /// A bounded draw of an integer at most 32 bits wide, such as [`Rng::u32`] or
+    /// [`Rng::roll_die`].
+    U32,
+    /// A bounded [`Rng::u64`] or [`Rng::i64`] draw, or a [`Rng::f64`].
+    U64,
+    /// A bounded [`Rng::u128`] or [`Rng::i128`] draw.
+    U128,
+    /// Filling the given number of random bytes, as [`SecureRng::bytes_vec`] does.
+    Bytes(usize),
+    /// A [`Rng::shuffle`] of a slice of the given length.
+    Shuffle(usize),
+}
+
+impl SecureRng {
+    /// Estimates how many bits of the stream `operation` consumes, so that contracts can
+    /// budget their randomness up front.
+    ///
+    /// Bounded draws use rejection sampling and redraw when they hit the rejected zone, so
+    /// the estimate is a lower bound. Redraws are rare unless a range spans a large share of
+    /// the draw width.
+    pub fn estimate_bits(operation: RngOp) -> u64 {
+        const WORD_BITS: u64 = u32::BITS as u64;
+        match operation {
+            RngOp::U32 => WORD_BITS,
+            RngOp::U64 => 2 * WORD_BITS,
+            RngOp::U128 => 4 * WORD_BITS,
+            RngOp::Bytes(len) => (len as u64).div_ceil(4).saturating_mul(WORD_BITS),
+            // Each swap draws an index below the current length, as a 32-bit value.
+            RngOp::Shuffle(len) => (len as u64).saturating_sub(1).saturating_mul(WORD_BITS),
+        }
+    }
+
+    /// Returns an [`AuditRecord`] of the current block and position in the stream.
+    pub fn audit_record(&self) -> AuditRecord {
+        AuditRecord {
+            block_height: env::block_height(),
+            random_seed: env::random_seed_array().into(),
+            seed: self.inner.get_seed().into(),
+            word_pos: self.inner.get_word_pos().into(),
+        }
+    }
+
+    /// Recreates the generator an [`AuditRecord`] was taken from, at the position it was
+    /// taken, so that the draws made after the record can be reproduced.
+    pub fn replay(record: &AuditRecord) -> Self {
+        let mut rng = Self::from_seed(record.seed.into());
+        rng.inner.set_word_pos(record.word_pos.0);
+        rng
+    }
+
+    /// Returns a uniformly distributed `u128` in `range`, logging it together with the
+    /// generator's position in the stream when it is at least `log_threshold`.
+    ///
+    /// Useful to keep an audit trail of high-value draws only. Panics if `range` is empty.
+    pub fn draw_u128_logged_above(&mut self, range: Range<u128>, log_threshold: u128) -> u128 {
+        let word_pos = self.inner.get_word_pos();
+        let value = self.u128(range);
+        if value >= log_threshold {
+            env::log_str(&format!(
+                "SecureRng draw of {value} at word {word_pos} met threshold {log_threshold}"
+            ));
+        }
+        value
+    }
+
+    /// Returns `sha256(seed || draws)`, with every draw encoded as little-endian bytes, as a
+    /// commitment to the outcome of a finished game.
+    ///
+    /// Store the commitment when the game ends; once `seed` is published, anyone can replay
+    /// the draws with [`SecureRng::from_seed`] and check them against it.
+    pub fn result_commitment(seed: [u8; 32], draws: &[u64]) -> [u8; 32] {
+        let mut preimage = Vec::with_capacity(32 + draws.len() * 8);
+        preimage.extend_from_slice(&seed);
+        for draw in draws {
+            preimage.extend_from_slice(&draw.to_le_bytes());
+        }
+        env::sha256_array(&preimage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::RngCore;
+    use crate::test_utils::VMContextBuilder;
+
+    #[test]
+    fn record_matches_context() {
+        crate::testing_env!(VMContextBuilder::new().block_height(77).random_seed([3; 32]).build());
+        let mut rng = SecureRng::new();
+        let before = rng.audit_record();
+        rng.next_u32();
+        let after = rng.audit_record();
+
+        assert_eq!(before.block_height, 77);
+        assert_eq!(before.random_seed, [3; 32]);
+        assert_eq!(before.seed, rng.seed());
+        assert_ne!(before.seed, [3; 32]);
+        assert_eq!(before.word_pos.0, 0);
+        assert_eq!(after.word_pos.0, 1);
+    }
+
+    #[test]
+    fn record_replays_next_draws() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([6; 32]).build());
+        for mut rng in [SecureRng::new(), SecureRng::from_game_id("game-7")] {
+            rng.bytes_vec(21);
+            let record = rng.audit_record();
+            assert_eq!(SecureRng::replay(&record), rng);
+            assert_eq!(SecureRng::replay(&record).u32(0..100), rng.u32(0..100));
+        }
+    }
+
+    #[test]
+    fn only_draws_above_threshold_are_logged() {
+        crate::testing_env!(VMContextBuilder::new().build());
+        let mut rng = SecureRng::new();
+        assert!(rng.draw_u128_logged_above(0..100, 100) < 100);
+        assert!(crate::test_utils::get_logs().is_empty());
+
+        let word = rng.audit_record().word_pos.0;
+        let value = rng.draw_u128_logged_above(1000..2000, 1000);
+        assert_eq!(
+            crate::test_utils::get_logs(),
+            [format!("SecureRng draw of {value} at word {word} met threshold 1000")]
+        );
+    }
+
+    #[test]
+    fn commitment_matches_replay() {
+        let seed = [9; 32];
+        let mut rng = SecureRng::from_seed(seed);
+        let draws: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        let commitment = SecureRng::result_commitment(seed, &draws);
+
+        let mut replay = SecureRng::from_seed(seed);
+        let replayed: Vec<u64> = (0..5).map(|_| replay.next_u64()).collect();
+        assert_eq!(SecureRng::result_commitment(seed, &replayed), commitment);
+
+        let mut tampered = draws.clone();
+        tampered[3] ^= 1;
+        assert_ne!(SecureRng::result_commitment(seed, &tampered), commitment);
+        assert_ne!(SecureRng::result_commitment([8; 32], &draws), commitment);
+        assert_ne!(SecureRng::result_commitment(seed, &draws[..4]), commitment);
+    }
+
+    fn consumed_bits(draw: impl FnOnce(&mut SecureRng)) -> u64 {
+        let mut rng = SecureRng::from_seed([4; 32]);
+        draw(&mut rng);
+        u64::try_from(rng.inner.get_word_pos()).unwrap() * 32
+    }
+
+    #[test]
+    fn estimates_match_consumed_words() {
+        for len in [0, 1, 2, 52, 1000] {
+            let mut deck: Vec<u32> = (0..len).collect();
+            assert_eq!(
+                consumed_bits(|rng| rng.shuffle(&mut deck)),
+                SecureRng::estimate_bits(RngOp::Shuffle(deck.len()))
+            );
+        }
+        let estimate = SecureRng::estimate_bits;
+        assert_eq!(consumed_bits(|rng| _ = rng.u32(1..=6)), estimate(RngOp::U32));
+        assert_eq!(consumed_bits(|rng| _ = rng.u64(..)), estimate(RngOp::U64));
+        assert_eq!(consumed_bits(|rng| _ = rng.f64()), estimate(RngOp::U64));
+        assert_eq!(consumed_bits(|rng| _ = rng.u128(0..1000)), estimate(RngOp::U128));
+        assert_eq!(consumed_bits(|rng| _ = rng.bytes_vec(13)), estimate(RngOp::Bytes(13)));
+        assert_eq!(estimate(RngOp::Shuffle(usize::MAX)), u64::MAX);
+    }
+
+    #[test]
+    fn record_serializes_to_json() {
+        crate::testing_env!(VMContextBuilder::new().block_height(5).build());
+        let value = serde_json::to_value(SecureRng::new().audit_record()).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["block_height", "random_seed", "seed", "word_pos"]);
+        assert_eq!(value["block_height"], 5);
+        assert_eq!(value["word_pos"], "0");
+        assert_eq!(value["random_seed"], "11111111111111111111111111111111");
+    }
+}
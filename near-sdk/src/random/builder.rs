@@ -0,0 +1,136 @@
+use super::SecureRng;
+use crate::env;
+
+/// Collects entropy inputs and derives a [`SecureRng`] from all of them at once.
+///
+/// Inputs are concatenated in the order they are added and hashed when the generator is
+/// built, so the same composition in the same context always yields the same stream.
+/// [`SecureRng::new`] is equivalent to
+/// `SecureRngBuilder::new().with_block_seed().with_account_context().build()`.
+///
+/// Variable-length inputs are not delimited, so combine them with fixed-length ones (or a
+/// length added with [`with_u64`](Self::with_u64)) when their boundaries matter.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::random::{Rng, SecureRngBuilder};
+///
+/// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+/// let commitment = [7u8; 32];
+/// let nonce = 42;
+/// let mut rng = SecureRngBuilder::new()
+///     .with_block_seed()
+///     .with_account_context()
+///     .with_bytes(&commitment)
+///     .with_u64(nonce)
+///     .build();
+/// let roll = rng.roll_die(6);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SecureRngBuilder {
+    entropy: Vec<u8>,
+}
+
+impl SecureRngBuilder {
+    /// Creates a builder without any entropy.
+    ///
+    /// A generator built without adding the block seed is fully determined by the inputs
+    /// given to the builder, so anyone who knows them can predict its output.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the block random seed ([`env::random_seed_array`]).
+    pub fn with_block_seed(mut self) -> Self {
+        self.entropy.extend_from_slice(&env::random_seed_array());
+        self
+    }
+
+    /// Adds the current transaction context: current and predecessor accounts, signer public
+    /// key, block height, prepaid gas and gas used so far.
+    pub fn with_account_context(mut self) -> Self {
+        self.entropy.extend_from_slice(env::current_account_id().as_str().as_bytes());
+        self.entropy.extend_from_slice(env::predecessor_account_id().as_str().as_bytes());
+        self.entropy.extend_from_slice(env::signer_account_pk().as_bytes());
+        self.entropy.extend_from_slice(&env::block_height().to_le_bytes());
+        self.entropy.extend_from_slice(&env::prepaid_gas().as_gas().to_le_bytes());
+        // Gas used so far grows with every host call, so it acts as a counter that separates
+        // generators created one after another in the same call.
+        self.entropy.extend_from_slice(&env::used_gas().as_gas().to_le_bytes());
+        self
+    }
+
+    /// Adds arbitrary bytes, such as a value committed by a user or a promise result.
+    pub fn with_bytes(mut self, bytes: &[u8]) -> Self {
+        self.entropy.extend_from_slice(bytes);
+        self
+    }
+
+    /// Adds `value` as little-endian bytes, for example a nonce.
+    pub fn with_u64(self, value: u64) -> Self {
+        self.with_bytes(&value.to_le_bytes())
+    }
+
+    /// Derives the generator from the collected inputs.
+    pub fn build(self) -> SecureRng {
+        SecureRng::from_entropy(&self.entropy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::RngCore;
+    use crate::test_utils::VMContextBuilder;
+
+    fn first_words(builder: SecureRngBuilder) -> [u64; 2] {
+        let mut rng = builder.build();
+        [rng.next_u64(), rng.next_u64()]
+    }
+
+    #[test]
+    fn same_composition_is_reproducible() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([4; 32]).build());
+        let compose =
+            || SecureRngBuilder::new().with_block_seed().with_bytes(b"commit").with_u64(7);
+        assert_eq!(first_words(compose()), first_words(compose()));
+        assert_eq!(
+            first_words(SecureRngBuilder::new().with_block_seed().with_bytes(b"commitment")),
+            first_words(SecureRngBuilder::new().with_block_seed().with_bytes(b"commitment")),
+        );
+    }
+
+    #[test]
+    fn different_compositions_differ() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([5; 32]).build());
+        let outputs = [
+            first_words(SecureRngBuilder::new()),
+            first_words(SecureRngBuilder::new().with_block_seed()),
+            first_words(SecureRngBuilder::new().with_block_seed().with_u64(1)),
+            first_words(SecureRngBuilder::new().with_block_seed().with_u64(2)),
+            first_words(SecureRngBuilder::new().with_u64(1).with_block_seed()),
+            first_words(SecureRngBuilder::new().with_block_seed().with_bytes(b"promise")),
+        ];
+        for (i, a) in outputs.iter().enumerate() {
+            for b in &outputs[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn new_matches_builder_configuration() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([6; 32]).build());
+        let from_new = SecureRng::new().next_u64();
+        crate::testing_env!(VMContextBuilder::new().random_seed([6; 32]).build());
+        let mut built = SecureRngBuilder::new().with_block_seed().with_account_context().build();
+        assert_eq!(built.next_u64(), from_new);
+
+        crate::testing_env!(VMContextBuilder::new().random_seed([6; 32]).build());
+        assert_eq!(
+            SecureRng::with_entropy(b"extra").next_u64(),
+            SecureRngBuilder::new().with_block_seed().with_bytes(b"extra").build().next_u64(),
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Playing cards for game contracts.
+//!
+//! [`Card`] and its parts are Borsh and JSON serializable, so decks and hands can be kept in
+//! contract state and returned from view methods.
+
+use near_sdk_macros::near;
+
+use super::{Rng, SecureRng};
+
+/// Suit of a playing card.
+#[near(inside_nearsdk, serializers = [json, borsh])]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    /// All suits in ascending order.
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+}
+
+/// Rank of a playing card, with aces high.
+#[near(inside_nearsdk, serializers = [json, borsh])]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    /// All ranks in ascending order.
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+}
+
+/// A card from a standard 52-card deck.
+#[near(inside_nearsdk, serializers = [json, borsh])]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card {
+    pub suit: Suit,
+    pub rank: Rank,
+}
+
+/// Returns the 52 cards of a standard deck, ordered by suit and then rank.
+pub fn standard_deck() -> Vec<Card> {
+    Suit::ALL
+        .into_iter()
+        .flat_map(|suit| Rank::ALL.into_iter().map(move |rank| Card { suit, rank }))
+        .collect()
+}
+
+impl SecureRng {
+    /// Returns a shuffled standard 52-card deck.
+    pub fn new_shuffled_deck(&mut self) -> Vec<Card> {
+        let mut deck = standard_deck();
+        self.shuffle(&mut deck);
+        deck
+    }
+
+    /// Shuffles `deck` and deals `n` cards off the top, removing them from the deck.
+    ///
+    /// The top of the deck is its first element. Deals the whole deck if it holds fewer than
+    /// `n` cards.
+    pub fn deal(&mut self, deck: &mut Vec<Card>, n: usize) -> Vec<Card> {
+        self.shuffle(deck);
+        deck.drain(..n.min(deck.len())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::test_rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn shuffled_deck_has_52_distinct_cards() {
+        let deck = test_rng(1).new_shuffled_deck();
+        assert_eq!(deck.len(), 52);
+        assert_eq!(deck.iter().collect::<HashSet<_>>().len(), 52);
+        assert_ne!(deck, standard_deck());
+    }
+
+    #[test]
+    fn dealt_hand_leaves_the_deck() {
+        let mut rng = test_rng(3);
+        let mut deck = standard_deck();
+        let hand = rng.deal(&mut deck, 5);
+        assert_eq!(hand.len(), 5);
+        assert_eq!(hand.iter().collect::<HashSet<_>>().len(), 5);
+        assert_eq!(deck.len(), 47);
+        assert!(hand.iter().all(|card| !deck.contains(card)));
+
+        let second = rng.deal(&mut deck, 5);
+        assert!(second.iter().all(|card| !hand.contains(card)));
+        assert_eq!(deck.len(), 42);
+        assert_eq!(rng.deal(&mut deck, 100).len(), 42);
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn deck_persists_through_borsh() {
+        let mut rng = test_rng(4);
+        let mut deck = rng.new_shuffled_deck();
+        rng.deal(&mut deck, 3);
+        let restored: Vec<Card> = borsh::from_slice(&borsh::to_vec(&deck).unwrap()).unwrap();
+        assert_eq!(restored, deck);
+    }
+
+    #[test]
+    fn shuffled_deck_is_reproducible_per_seed() {
+        assert_eq!(test_rng(1).new_shuffled_deck(), test_rng(1).new_shuffled_deck());
+        assert_ne!(test_rng(1).new_shuffled_deck(), test_rng(2).new_shuffled_deck());
+    }
+}
@@ -0,0 +1,136 @@
+use near_sdk_macros::near;
+
+use super::{SecureRng, SecureRngBuilder};
+use crate::env;
+
+/// Domain tag for generators derived by [`CommitReveal::reveal`].
+const COMMIT_REVEAL_DOMAIN: &[u8] = b"near-sdk:random:commit-reveal:";
+
+/// Commit-reveal helper that seeds a [`SecureRng`] from a secret committed in an earlier call.
+///
+/// The participant first commits to `sha256(secret)` and reveals the secret in a later block.
+/// The generator mixes the secret with the block random seed of the reveal, which was unknown
+/// at commit time, so the participant cannot grind secrets for a favorable outcome, and the
+/// block producer cannot predict the outcome without the secret.
+///
+/// The helper is Borsh-serializable, so the pending commitment can be kept in contract state.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::env;
+/// use near_sdk::random::{CommitReveal, Rng};
+///
+/// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+/// let mut draw = CommitReveal::new();
+/// draw.commit(env::sha256_array(b"my secret"));
+/// // ... in a later block ...
+/// let mut rng = draw.reveal(b"my secret").expect("secret matches the commitment");
+/// let roll = rng.roll_die(6);
+/// ```
+#[near(inside_nearsdk)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitReveal {
+    commitment: Option<[u8; 32]>,
+}
+
+impl CommitReveal {
+    /// Creates a helper without a pending commitment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `hash`, the SHA-256 of the secret to be revealed later, replacing any pending
+    /// commitment.
+    pub fn commit(&mut self, hash: [u8; 32]) {
+        self.commitment = Some(hash);
+    }
+
+    /// Returns the pending commitment, if any.
+    pub fn commitment(&self) -> Option<[u8; 32]> {
+        self.commitment
+    }
+
+    /// Checks `secret` against the pending commitment and, if it matches, clears the
+    /// commitment and returns a generator seeded from the secret and the block random seed.
+    ///
+    /// The commitment is kept if the secret does not match.
+    pub fn reveal(&mut self, secret: &[u8]) -> Result<SecureRng, RevealError> {
+        let commitment = self.commitment.ok_or(RevealError::NoCommitment)?;
+        if env::sha256_array(secret) != commitment {
+            return Err(RevealError::Mismatch);
+        }
+        self.commitment = None;
+        Ok(SecureRngBuilder::new()
+            .with_bytes(COMMIT_REVEAL_DOMAIN)
+            .with_block_seed()
+            .with_bytes(secret)
+            .build())
+    }
+}
+
+/// Error returned by [`CommitReveal::reveal`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RevealError {
+    /// Nothing was committed, or the commitment was already revealed.
+    NoCommitment,
+    /// The hash of the revealed secret differs from the commitment.
+    Mismatch,
+}
+
+impl std::fmt::Display for RevealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevealError::NoCommitment => write!(f, "no pending commitment to reveal"),
+            RevealError::Mismatch => write!(f, "revealed secret does not match the commitment"),
+        }
+    }
+}
+
+impl std::error::Error for RevealError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, RngCore};
+    use crate::test_utils::VMContextBuilder;
+
+    fn committed(secret: &[u8]) -> CommitReveal {
+        let mut draw = CommitReveal::new();
+        draw.commit(env::sha256_array(secret));
+        draw
+    }
+
+    #[test]
+    fn valid_reveal_produces_rng() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([1; 32]).build());
+        let mut draw = committed(b"secret");
+        let mut rng = draw.reveal(b"secret").unwrap();
+        assert!((1..=6).contains(&rng.roll_die(6)));
+        assert_eq!(draw.commitment(), None);
+        assert_eq!(draw.reveal(b"secret").err(), Some(RevealError::NoCommitment));
+
+        let first = rng.next_u64();
+        let mut again = committed(b"secret").reveal(b"secret").unwrap();
+        again.roll_die(6);
+        assert_eq!(again.next_u64(), first);
+        let mut other = committed(b"other").reveal(b"other").unwrap();
+        other.roll_die(6);
+        assert_ne!(other.next_u64(), first);
+
+        crate::testing_env!(VMContextBuilder::new().random_seed([2; 32]).build());
+        let mut later = committed(b"secret").reveal(b"secret").unwrap();
+        later.roll_die(6);
+        assert_ne!(later.next_u64(), first);
+    }
+
+    #[test]
+    fn invalid_reveal_is_rejected() {
+        crate::testing_env!(VMContextBuilder::new().build());
+        let mut draw = committed(b"secret");
+        assert_eq!(draw.reveal(b"guess").err(), Some(RevealError::Mismatch));
+        assert_eq!(draw.commitment(), Some(env::sha256_array(b"secret")));
+        assert!(draw.reveal(b"secret").is_ok());
+        assert_eq!(CommitReveal::new().reveal(b"secret").err(), Some(RevealError::NoCommitment));
+    }
+}
@@ -0,0 +1,567 @@
+use std::f64::consts::PI;
+use std::ops::{Range, RangeInclusive};
+
+use super::{Rng, SecureRng, gen_in};
+use crate::env;
+
+/// A probability distribution over values of type `T`, drawn with [`SecureRng::sample`].
+///
+/// Implemented for integer ranges, [`Bernoulli`] and
+/// [`WeightedSampler`](super::WeightedSampler), and meant to be implemented for custom
+/// distributions so that code can be generic over how values are drawn.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::random::{Distribution, Rng, SecureRng};
+///
+/// /// Sum of two six-sided dice, capped at ten.
+/// struct CappedDice;
+///
+/// impl Distribution<u32> for CappedDice {
+///     fn sample(&self, rng: &mut SecureRng) -> u32 {
+///         rng.roll_dice(2, 6).min(10)
+///     }
+/// }
+///
+/// fn draw<D: Distribution<u32>>(distribution: &D, rng: &mut SecureRng) -> u32 {
+///     rng.sample(distribution)
+/// }
+///
+/// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+/// let mut rng = SecureRng::new();
+/// assert!((2..=10).contains(&draw(&CappedDice, &mut rng)));
+/// assert!((1..7).contains(&draw(&(1..7), &mut rng)));
+/// ```
+pub trait Distribution<T> {
+    /// Draws a value from the distribution.
+    fn sample(&self, rng: &mut SecureRng) -> T;
+}
+
+macro_rules! impl_range_distribution {
+    ($($ty:ty),*) => {$(
+        impl Distribution<$ty> for Range<$ty> {
+            fn sample(&self, rng: &mut SecureRng) -> $ty {
+                gen_in(rng, self.clone())
+            }
+        }
+
+        impl Distribution<$ty> for RangeInclusive<$ty> {
+            fn sample(&self, rng: &mut SecureRng) -> $ty {
+                gen_in(rng, self.clone())
+            }
+        }
+    )*};
+}
+
+impl_range_distribution!(u8, u16, u32, u64, u128, usize, i32, i64, i128);
+
+/// Distribution of `true` with a fixed probability, drawn like [`Rng::chance`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bernoulli {
+    probability: f64,
+}
+
+impl Bernoulli {
+    /// Creates a distribution that is `true` with `probability`, clamped to `[0.0, 1.0]`. A
+    /// NaN probability is stored as `0.0`, so it never succeeds.
+    pub fn new(probability: f64) -> Self {
+        let probability = if probability.is_nan() { 0.0 } else { probability.clamp(0.0, 1.0) };
+        Self { probability }
+    }
+}
+
+impl Distribution<bool> for Bernoulli {
+    fn sample(&self, rng: &mut SecureRng) -> bool {
+        rng.chance(self.probability)
+    }
+}
+
+impl SecureRng {
+    /// Draws a value from `distribution`.
+    pub fn sample<T, D: Distribution<T> + ?Sized>(&mut self, distribution: &D) -> T {
+        distribution.sample(self)
+    }
+
+    /// Returns two uniform values in `[0.0, 1.0]` whose rank (Spearman) correlation is
+    /// `correlation`, clamped to `[-1.0, 1.0]`.
+    ///
+    /// The pair is drawn from a Gaussian copula: two correlated standard normals are mapped
+    /// through the normal CDF, which keeps each marginal uniform.
+    pub fn gaussian_copula_pair(&mut self, correlation: f64) -> (f64, f64) {
+        let rank = if correlation.is_nan() { 0.0 } else { correlation.clamp(-1.0, 1.0) };
+        // Pearson correlation of the underlying normals that yields the requested rank
+        // correlation of the uniforms.
+        let rho = 2.0 * (PI * rank / 6.0).sin();
+        let x = self.standard_normal();
+        let y = rho * x + (1.0 - rho * rho).max(0.0).sqrt() * self.standard_normal();
+        (normal_cdf(x), normal_cdf(y))
+    }
+
+    /// Draws a value from the distribution whose cumulative distribution function passes
+    /// through the points `(xs[i], cdf[i])`, interpolating linearly in between.
+    ///
+    /// The `cdf` values are normalized by the last one; any mass below `cdf[0]` is placed at
+    /// `xs[0]`. Results therefore always lie in `[xs[0], xs[xs.len() - 1]]`.
+    ///
+    /// Panics unless both slices have the same length of at least two, are non-decreasing, and
+    /// the last `cdf` value is positive.
+    pub fn sample_interpolated_cdf(&mut self, xs: &[f64], cdf: &[f64]) -> f64 {
+        let non_decreasing = |values: &[f64]| values.windows(2).all(|pair| pair[0] <= pair[1]);
+        if xs.len() != cdf.len() || xs.len() < 2 {
+            env::panic_str("sample_interpolated_cdf: expected at least two points of each");
+        }
+        if !non_decreasing(xs) || !non_decreasing(cdf) || cdf[0] < 0.0 {
+            env::panic_str("sample_interpolated_cdf: points must be non-negative and sorted");
+        }
+        let last = cdf[cdf.len() - 1];
+        if last <= 0.0 || last.is_infinite() {
+            env::panic_str("sample_interpolated_cdf: the last cdf value must be positive");
+        }
+
+        let target = self.f64() * last;
+        // First point whose cdf exceeds the target; the segment ends there.
+        let end = cdf.partition_point(|&value| value <= target);
+        if end == 0 {
+            return xs[0];
+        }
+        if end == cdf.len() {
+            return xs[xs.len() - 1];
+        }
+        let fraction = (target - cdf[end - 1]) / (cdf[end] - cdf[end - 1]);
+        xs[end - 1] + fraction * (xs[end] - xs[end - 1])
+    }
+
+    /// Returns a `(latitude, longitude)` pair drawn uniformly from `lat_range` and `lon_range`.
+    ///
+    /// A range with equal bounds yields that bound. Panics if a range has non-finite bounds or
+    /// a start greater than its end.
+    pub fn random_coord(&mut self, lat_range: Range<f64>, lon_range: Range<f64>) -> (f64, f64) {
+        (self.coordinate(lat_range), self.coordinate(lon_range))
+    }
+
+    fn coordinate(&mut self, range: Range<f64>) -> f64 {
+        if !range.start.is_finite() || !range.end.is_finite() || range.start > range.end {
+            env::panic_str("random_coord: ranges must be finite and not reversed");
+        }
+        if range.is_empty() { range.start } else { self.f64_range(range) }
+    }
+
+    /// Returns a normally distributed value with the given `mean` and `std_dev`, drawn with
+    /// the Box-Muller transform. Returns `mean` if `std_dev` is not positive.
+    pub fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+        if std_dev.is_nan() || std_dev <= 0.0 {
+            return mean;
+        }
+        mean + std_dev * self.standard_normal()
+    }
+
+    /// Returns a normally distributed value with the given `mean` and `std_dev`, truncated to
+    /// `[lo, hi]` by redrawing values that fall outside the bounds, so that the density near
+    /// the bounds keeps its shape instead of piling up as with clamping.
+    ///
+    /// Gives up after 64 rejected draws, which only happens when the bounds hold a tiny share
+    /// of the distribution, and returns a uniform value in `[lo, hi]` instead. Returns `mean`
+    /// clamped to the bounds if `std_dev` is not positive. Panics if the bounds are not finite
+    /// or `lo > hi`.
+    pub fn truncated_normal(&mut self, mean: f64, std_dev: f64, lo: f64, hi: f64) -> f64 {
+        const MAX_ATTEMPTS: usize = 64;
+        if !lo.is_finite() || !hi.is_finite() || lo > hi {
+            env::panic_str("truncated_normal: bounds must be finite and not reversed");
+        }
+        if std_dev.is_nan() || std_dev <= 0.0 {
+            return mean.clamp(lo, hi);
+        }
+        for _ in 0..MAX_ATTEMPTS {
+            let value = self.normal(mean, std_dev);
+            if (lo..=hi).contains(&value) {
+                return value;
+            }
+        }
+        lo + self.f64() * (hi - lo)
+    }
+
+    /// Returns the number of events in an interval where `lambda` events are expected, drawn
+    /// from a Poisson distribution with Knuth's algorithm.
+    ///
+    /// The algorithm makes one draw per counted event, so gas grows linearly with `lambda`,
+    /// which is clamped to 500 to bound it. Returns 0 if `lambda` is not positive.
+    pub fn poisson(&mut self, lambda: f64) -> u32 {
+        const MAX_LAMBDA: f64 = 500.0;
+        if lambda.is_nan() || lambda <= 0.0 {
+            return 0;
+        }
+        let limit = (-lambda.min(MAX_LAMBDA)).exp();
+        let mut count = 0;
+        let mut product = self.f64();
+        while product >= limit {
+            count += 1;
+            product *= self.f64();
+        }
+        count
+    }
+
+    /// Returns a price path of `steps + 1` prices starting at `start`, where every step
+    /// multiplies the previous price by `exp(normal(0, volatility))`, as in geometric Brownian
+    /// motion without drift.
+    ///
+    /// Prices never reach zero, even with extreme volatility. The path is flat if `volatility`
+    /// is not positive. Panics if `start` is not positive and finite.
+    pub fn random_walk_prices(&mut self, start: f64, steps: usize, volatility: f64) -> Vec<f64> {
+        if !start.is_finite() || start <= 0.0 {
+            env::panic_str("random_walk_prices: start must be positive and finite");
+        }
+        let mut prices = Vec::with_capacity(steps + 1);
+        let mut price = start;
+        prices.push(price);
+        for _ in 0..steps {
+            price = (price * self.normal(0.0, volatility).exp()).max(f64::MIN_POSITIVE);
+            prices.push(price);
+        }
+        prices
+    }
+
+    /// Returns proportions summing to `1.0` drawn from a Dirichlet distribution with the given
+    /// concentration parameters, one per component.
+    ///
+    /// Larger alphas spread mass more evenly between components, while alphas below one favor
+    /// a few dominant components. Panics if an alpha is not positive and finite.
+    pub fn dirichlet(&mut self, alphas: &[f64]) -> Vec<f64> {
+        if alphas.iter().any(|alpha| !alpha.is_finite() || *alpha <= 0.0) {
+            env::panic_str("dirichlet: alphas must be positive and finite");
+        }
+        let logs: Vec<f64> = alphas.iter().map(|&alpha| self.ln_gamma_draw(alpha)).collect();
+        // Small alphas give gamma draws that underflow to zero, so they are normalized in log
+        // space relative to the largest one, which becomes exactly one.
+        let largest = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if largest == f64::NEG_INFINITY && !alphas.is_empty() {
+            // Even the logarithms underflowed, which only extreme alphas cause: all the mass
+            // goes to one component, as the distribution nearly always does for them.
+            let mut proportions = vec![0.0; alphas.len()];
+            proportions[self.usize(0..alphas.len())] = 1.0;
+            return proportions;
+        }
+        let draws: Vec<f64> = logs.into_iter().map(|log| (log - largest).exp()).collect();
+        let total: f64 = draws.iter().sum();
+        draws.into_iter().map(|draw| draw / total).collect()
+    }
+
+    /// Returns the logarithm of a draw from a gamma distribution with unit scale, using
+    /// Marsaglia and Tsang's method.
+    fn ln_gamma_draw(&mut self, shape: f64) -> f64 {
+        if shape < 1.0 {
+            // Boost the shape above one and scale the result back down.
+            let ln_boost = (1.0 - self.f64()).ln() / shape;
+            return self.ln_gamma_draw(shape + 1.0) + ln_boost;
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let x = self.standard_normal();
+            let v = (1.0 + c * x).powi(3);
+            if v <= 0.0 {
+                continue;
+            }
+            let u = 1.0 - self.f64();
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+                return (d * v).ln();
+            }
+        }
+    }
+
+    /// Draws a standard normal value using the Box-Muller transform.
+    pub(super) fn standard_normal(&mut self) -> f64 {
+        // `1.0 - f64()` lies in `(0.0, 1.0]`, keeping the logarithm finite.
+        let radius = (-2.0 * (1.0 - self.f64()).ln()).sqrt();
+        radius * (2.0 * PI * self.f64()).cos()
+    }
+}
+
+/// Standard normal cumulative distribution function.
+pub(super) fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Error function approximation from Abramowitz and Stegun (formula 7.1.26), accurate to
+/// about `1.5e-7`.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bernoulli, Distribution, normal_cdf};
+    use crate::random::{SecureRng, WeightedSampler, test_rng};
+
+    fn correlation(pairs: &[(f64, f64)]) -> f64 {
+        let n = pairs.len() as f64;
+        let (mx, my) = pairs.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (mx, my) = (mx / n, my / n);
+        let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+        for (x, y) in pairs {
+            sxy += (x - mx) * (y - my);
+            sxx += (x - mx) * (x - mx);
+            syy += (y - my) * (y - my);
+        }
+        sxy / (sxx * syy).sqrt()
+    }
+
+    struct Constant(u32);
+
+    impl Distribution<u32> for Constant {
+        fn sample(&self, _rng: &mut SecureRng) -> u32 {
+            self.0
+        }
+    }
+
+    /// Sum of two draws from `inner`, capped at `cap`.
+    struct CappedSum<D> {
+        inner: D,
+        cap: u32,
+    }
+
+    impl<D: Distribution<u32>> Distribution<u32> for CappedSum<D> {
+        fn sample(&self, rng: &mut SecureRng) -> u32 {
+            (rng.sample(&self.inner) + rng.sample(&self.inner)).min(self.cap)
+        }
+    }
+
+    fn draw_many<D: Distribution<u32>>(distribution: &D, rng: &mut SecureRng) -> Vec<u32> {
+        (0..1000).map(|_| rng.sample(distribution)).collect()
+    }
+
+    #[test]
+    fn custom_distributions_compose() {
+        let mut rng = test_rng(14);
+        assert!(draw_many(&Constant(7), &mut rng).iter().all(|&value| value == 7));
+
+        let dice = CappedSum { inner: 1..=6, cap: 10 };
+        let sums = draw_many(&dice, &mut rng);
+        assert!(sums.iter().all(|&sum| (2..=10).contains(&sum)));
+        // A capped 10 absorbs 10, 11 and 12, so it is the most frequent sum after 7.
+        let tens = sums.iter().filter(|&&sum| sum == 10).count();
+        let nines = sums.iter().filter(|&&sum| sum == 9).count();
+        assert!(tens > nines, "{tens} vs {nines}");
+
+        let nested = CappedSum { inner: Constant(4), cap: 5 };
+        assert_eq!(rng.sample(&nested), 5);
+    }
+
+    #[test]
+    fn builtin_distributions_implement_trait() {
+        let mut rng = test_rng(15);
+        assert!((10..20).contains(&rng.sample(&(10u64..20))));
+        assert_eq!(rng.sample(&(i32::MAX..=i32::MAX)), i32::MAX);
+
+        let coin = Bernoulli::new(0.25);
+        let heads = (0..4000).filter(|_| rng.sample(&coin)).count();
+        assert!((900..1100).contains(&heads), "{heads}");
+        assert!(!rng.sample(&Bernoulli::new(-1.0)));
+        assert!(rng.sample(&Bernoulli::new(2.0)));
+        assert_eq!(Bernoulli::new(7.0), Bernoulli::new(1.0));
+        assert_eq!(Bernoulli::new(-0.5), Bernoulli::new(0.0));
+        assert_eq!(Bernoulli::new(f64::NAN), Bernoulli::new(0.0));
+
+        let sampler = WeightedSampler::new(&[0, 3, 0]);
+        assert_eq!(Distribution::sample(&sampler, &mut rng), 1);
+        assert_eq!(rng.sample(&sampler), 1);
+    }
+
+    #[test]
+    fn normal_cdf_known_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.96) - 0.025).abs() < 1e-4);
+    }
+
+    #[test]
+    fn copula_correlation_approaches_requested() {
+        let mut rng = test_rng(1);
+        for requested in [-0.5, 0.0, 0.7] {
+            let pairs: Vec<_> = (0..20_000).map(|_| rng.gaussian_copula_pair(requested)).collect();
+            assert!(
+                pairs.iter().all(|&(u, v)| (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v))
+            );
+            let observed = correlation(&pairs);
+            assert!((observed - requested).abs() < 0.05, "{requested} vs {observed}");
+        }
+    }
+
+    #[test]
+    fn interpolated_cdf_follows_density() {
+        let mut rng = test_rng(3);
+        let xs = [0.0, 1.0, 2.0];
+        let cdf = [0.0, 0.8, 1.0];
+        let samples: Vec<f64> =
+            (0..20_000).map(|_| rng.sample_interpolated_cdf(&xs, &cdf)).collect();
+        assert!(samples.iter().all(|x| (0.0..=2.0).contains(x)));
+        let below_one = samples.iter().filter(|&&x| x < 1.0).count() as f64 / 20_000.0;
+        assert!((below_one - 0.8).abs() < 0.02, "{below_one}");
+        let below_half = samples.iter().filter(|&&x| x < 0.5).count() as f64 / 20_000.0;
+        assert!((below_half - 0.4).abs() < 0.02, "{below_half}");
+    }
+
+    #[test]
+    #[should_panic(expected = "must be non-negative and sorted")]
+    fn interpolated_cdf_rejects_unsorted_points() {
+        test_rng(4).sample_interpolated_cdf(&[0.0, 2.0, 1.0], &[0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn coordinates_fall_within_ranges() {
+        let mut rng = test_rng(6);
+        for _ in 0..1000 {
+            let (lat, lon) = rng.random_coord(-90.0..90.0, 10.5..11.0);
+            assert!((-90.0..90.0).contains(&lat) && (10.5..11.0).contains(&lon));
+        }
+        assert_eq!(rng.random_coord(45.0..45.0, -3.0..-3.0), (45.0, -3.0));
+
+        // The distance between these bounds overflows to infinity.
+        let wide: Vec<f64> =
+            (0..100).map(|_| rng.random_coord(-f64::MAX..f64::MAX, 0.0..1.0).0).collect();
+        assert!(wide.iter().all(|lat| lat.is_finite()));
+        assert!(wide.iter().any(|&lat| lat > 0.0) && wide.iter().any(|&lat| lat > -f64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "not reversed")]
+    fn reversed_coordinate_range() {
+        test_rng(7).random_coord(10.0..-10.0, 0.0..1.0);
+    }
+
+    #[test]
+    fn normal_matches_mean_and_variance() {
+        let mut rng = test_rng(10);
+        let samples: Vec<f64> = (0..10_000).map(|_| rng.normal(-4.0, 2.5)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!((mean + 4.0).abs() < 0.1, "{mean}");
+        assert!((variance - 6.25).abs() < 0.4, "{variance}");
+        assert_eq!(rng.normal(7.0, 0.0), 7.0);
+        assert_eq!(rng.normal(7.0, -1.0), 7.0);
+    }
+
+    #[test]
+    fn truncated_normal_keeps_shape_near_bounds() {
+        let mut rng = test_rng(12);
+        let (lo, hi) = (-1.0, 1.5);
+        let mut bins = [0u32; 10];
+        for _ in 0..20_000 {
+            let value = rng.truncated_normal(0.0, 1.0, lo, hi);
+            assert!((lo..=hi).contains(&value), "{value}");
+            bins[(((value - lo) / (hi - lo) * 10.0) as usize).min(9)] += 1;
+        }
+        let mass = normal_cdf(hi) - normal_cdf(lo);
+        for (i, &count) in bins.iter().enumerate() {
+            let (a, b) = (lo + 0.25 * i as f64, lo + 0.25 * (i + 1) as f64);
+            let expected = 20_000.0 * (normal_cdf(b) - normal_cdf(a)) / mass;
+            assert!((f64::from(count) / expected - 1.0).abs() < 0.1, "{i}: {bins:?}");
+        }
+
+        assert_eq!(rng.truncated_normal(5.0, 0.0, lo, hi), hi);
+        assert_eq!(rng.truncated_normal(0.3, 1.0, 0.3, 0.3), 0.3);
+        let far = rng.truncated_normal(0.0, 1.0, 50.0, 51.0);
+        assert!((50.0..=51.0).contains(&far));
+    }
+
+    #[test]
+    #[should_panic(expected = "truncated_normal: bounds must be finite and not reversed")]
+    fn truncated_normal_rejects_reversed_bounds() {
+        test_rng(13).truncated_normal(0.0, 1.0, 1.0, -1.0);
+    }
+
+    #[test]
+    fn poisson_mean_approximates_lambda() {
+        let mut rng = test_rng(18);
+        for lambda in [0.5, 4.0, 30.0] {
+            let total: u32 = (0..5_000).map(|_| rng.poisson(lambda)).sum();
+            let mean = f64::from(total) / 5_000.0;
+            assert!((mean - lambda).abs() < 0.05 * lambda + 0.05, "{lambda}: {mean}");
+        }
+        assert_eq!(rng.poisson(0.0), 0);
+        assert_eq!(rng.poisson(-2.0), 0);
+        assert_eq!(rng.poisson(f64::NAN), 0);
+        assert!(rng.poisson(f64::INFINITY) < 700);
+    }
+
+    #[test]
+    fn price_paths_stay_positive() {
+        let mut rng = test_rng(16);
+        let log_return_variance = |path: &[f64]| {
+            let returns: Vec<f64> = path.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64
+        };
+        let calm = rng.random_walk_prices(100.0, 1000, 0.01);
+        let wild = rng.random_walk_prices(100.0, 1000, 0.1);
+        for path in [&calm, &wild] {
+            assert_eq!(path.len(), 1001);
+            assert_eq!(path[0], 100.0);
+            assert!(path.iter().all(|&price| price > 0.0));
+        }
+        assert!(log_return_variance(&wild) > 50.0 * log_return_variance(&calm));
+
+        assert_eq!(rng.random_walk_prices(5.0, 0, 0.1), [5.0]);
+        assert_eq!(rng.random_walk_prices(5.0, 3, 0.0), [5.0; 4]);
+        assert!(rng.random_walk_prices(1.0, 50, 1e6).iter().all(|&price| price > 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "random_walk_prices: start must be positive and finite")]
+    fn price_path_rejects_non_positive_start() {
+        test_rng(17).random_walk_prices(0.0, 10, 0.1);
+    }
+
+    #[test]
+    fn dirichlet_proportions_sum_to_one() {
+        let mut rng = test_rng(8);
+        let spread = |rng: &mut SecureRng, alpha: f64| -> f64 {
+            let mut squares = 0.0;
+            for _ in 0..500 {
+                let proportions = rng.dirichlet(&[alpha; 3]);
+                assert_eq!(proportions.len(), 3);
+                assert!((proportions.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                assert!(proportions.iter().all(|p| (0.0..=1.0).contains(p)));
+                squares += proportions.iter().map(|p| (p - 1.0 / 3.0).powi(2)).sum::<f64>();
+            }
+            squares / 500.0
+        };
+        let (sparse, flat, concentrated) =
+            (spread(&mut rng, 0.3), spread(&mut rng, 1.0), spread(&mut rng, 100.0));
+        assert!(sparse > flat && flat > concentrated * 10.0, "{sparse} {flat} {concentrated}");
+        assert!(rng.dirichlet(&[]).is_empty());
+    }
+
+    #[test]
+    fn dirichlet_handles_tiny_alphas() {
+        let mut rng = test_rng(10);
+        for _ in 0..500 {
+            let proportions = rng.dirichlet(&[1e-3; 4]);
+            assert!(proportions.iter().all(|p| p.is_finite() && (0.0..=1.0).contains(p)));
+            assert!((proportions.iter().sum::<f64>() - 1.0).abs() < 1e-9, "{proportions:?}");
+        }
+        for _ in 0..100 {
+            let proportions = rng.dirichlet(&[f64::MIN_POSITIVE; 3]);
+            assert!((proportions.iter().sum::<f64>() - 1.0).abs() < 1e-9, "{proportions:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "positive and finite")]
+    fn dirichlet_rejects_zero_alpha() {
+        test_rng(9).dirichlet(&[1.0, 0.0]);
+    }
+
+    #[test]
+    fn copula_correlation_is_clamped() {
+        let mut rng = test_rng(2);
+        let pairs: Vec<_> = (0..1_000).map(|_| rng.gaussian_copula_pair(5.0)).collect();
+        assert!(correlation(&pairs) > 0.999);
+    }
+}
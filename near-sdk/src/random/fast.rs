@@ -0,0 +1,131 @@
+use super::{Rng, RngCore, SecureRngBuilder};
+
+/// Fast, non-cryptographic pseudo-random number generator implementing xoshiro256++.
+///
+/// **`FastRng` must never decide anything of value**, such as token outcomes, lottery
+/// winners or game results. Its output is predictable from a handful of earlier draws, so
+/// anyone observing it can compute every later draw. Use it only where a predictable result
+/// costs nothing, such as jittering display values or ordering items in a view. Use
+/// [`SecureRng`](super::SecureRng) for everything else.
+///
+/// All [`Rng`] methods are available, and draws cost a few arithmetic operations instead of a
+/// ChaCha20 block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastRng {
+    state: [u64; 4],
+}
+
+impl FastRng {
+    /// Creates a generator seeded from the block random seed and the current transaction
+    /// context, the same inputs as [`SecureRng::new`](super::SecureRng::new).
+    pub fn new() -> Self {
+        Self::from_seed(
+            SecureRngBuilder::new().with_block_seed().with_account_context().build().seed(),
+        )
+    }
+
+    /// Creates a generator from an explicit seed, for example to replay a stream in tests.
+    ///
+    /// An all-zero seed, which would make xoshiro output only zeros, is replaced with a fixed
+    /// non-zero state.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let mut state = [0u64; 4];
+        for (word, chunk) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        if state == [0; 4] {
+            state = [0x9e37_79b9_7f4a_7c15, 0xbf58_476d_1ce4_e5b9, 0x94d0_49bb_1331_11eb, 1];
+        }
+        Self { state }
+    }
+}
+
+impl Default for FastRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RngCore for FastRng {
+    fn next_u32(&mut self) -> u32 {
+        // The upper bits of xoshiro256++ are the strongest.
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s0.wrapping_add(*s3).rotate_left(23).wrapping_add(*s0);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl Rng for FastRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::VMContextBuilder;
+
+    #[test]
+    fn matches_reference_xoshiro256plusplus() {
+        let mut seed = [0; 32];
+        for (chunk, word) in seed.chunks_exact_mut(8).zip([1u64, 2, 3, 4]) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        let mut rng = FastRng::from_seed(seed);
+        let draws: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(draws, [41943041, 58720359, 3588806011781223]);
+    }
+
+    #[test]
+    fn rng_methods_stay_in_range() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([5; 32]).build());
+        let mut rng = FastRng::new();
+        for _ in 0..1000 {
+            assert!((10..20).contains(&rng.u8(10..20)));
+            assert!((10..=20).contains(&rng.u32(10..=20)));
+            assert!((-5..5).contains(&rng.i64(-5..5)));
+            assert!((0.0..1.0).contains(&rng.f64()));
+            assert!((1..=6).contains(&rng.roll_die(6)));
+        }
+        let mut items: Vec<u32> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+        assert!(rng.choice(&items).is_some());
+    }
+
+    #[test]
+    fn seeds_are_reproducible() {
+        let draws = |seed| {
+            let mut rng = FastRng::from_seed(seed);
+            (0..4).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draws([1; 32]), draws([1; 32]));
+        assert_ne!(draws([1; 32]), draws([2; 32]));
+        assert!(draws([0; 32]).iter().any(|&draw| draw != 0));
+
+        let mut bytes = [0; 13];
+        FastRng::from_seed([1; 32]).fill_bytes(&mut bytes);
+        assert_eq!(bytes[..8], draws([1; 32])[0].to_le_bytes());
+    }
+}
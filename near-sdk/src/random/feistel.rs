@@ -0,0 +1,91 @@
+use super::SecureRng;
+use crate::env;
+
+/// Domain tag for the round keys of [`SecureRng::feistel_permute`].
+const FEISTEL_DOMAIN: &[u8] = b"near-sdk:random:feistel:";
+
+impl SecureRng {
+    /// Maps `index` through a pseudo-random permutation of `0..2^domain_bits`, so that distinct
+    /// indices always map to distinct outputs without materializing the permutation.
+    ///
+    /// The permutation is a Feistel network with `rounds` rounds keyed by the generator's
+    /// current state, which it does not advance: every call made between two draws uses the
+    /// same permutation. Odd domain sizes are handled by cycle walking over the next even
+    /// one. At least four rounds are recommended; zero rounds is the identity.
+    ///
+    /// Panics if `domain_bits` exceeds 64 or `index` is outside the domain.
+    pub fn feistel_permute(&self, index: u64, domain_bits: u32, rounds: u32) -> u64 {
+        if domain_bits > 64 {
+            env::panic_str("feistel_permute: domain_bits must be at most 64");
+        }
+        let size = 1u128 << domain_bits;
+        if u128::from(index) >= size {
+            env::panic_str("feistel_permute: index is outside the domain");
+        }
+        let half_bits = domain_bits.div_ceil(2);
+        let mask = (1u64 << half_bits) - 1;
+        let key = env::sha256_array(
+            [FEISTEL_DOMAIN, &self.inner.get_seed(), &self.inner.get_word_pos().to_le_bytes()]
+                .concat(),
+        );
+        let round_output = |round: u32, half: u64| -> u64 {
+            let hash =
+                env::sha256_array([&key[..], &round.to_le_bytes(), &half.to_le_bytes()].concat());
+            u64::from_le_bytes(hash[..8].try_into().unwrap()) & mask
+        };
+
+        let mut value = index;
+        loop {
+            let (mut left, mut right) = (value >> half_bits, value & mask);
+            for round in 0..rounds {
+                (left, right) = (right, left ^ round_output(round, right));
+            }
+            value = left << half_bits | right;
+            if u128::from(value) < size {
+                return value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::{RngCore, SecureRng, test_rng};
+
+    #[test]
+    fn permutation_is_a_bijection() {
+        let rng = test_rng(1);
+        for bits in [0, 1, 5, 8, 11] {
+            let size = 1u64 << bits;
+            let mut hit = vec![false; size as usize];
+            for index in 0..size {
+                let output = rng.feistel_permute(index, bits, 4);
+                assert!(!hit[output as usize], "{output} hit twice for {bits} bits");
+                hit[output as usize] = true;
+            }
+            assert!(hit.iter().all(|&hit| hit));
+        }
+        let identity: Vec<u64> = (0..16).map(|index| rng.feistel_permute(index, 4, 0)).collect();
+        assert_eq!(identity, (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn permutation_is_keyed_by_stream_position() {
+        let mut rng = test_rng(2);
+        let permutation = |rng: &SecureRng| -> Vec<u64> {
+            (0..256).map(|index| rng.feistel_permute(index, 8, 4)).collect()
+        };
+        let before = permutation(&rng);
+        assert_ne!(before, (0..256).collect::<Vec<_>>());
+        assert_eq!(before, permutation(&rng));
+        rng.next_u32();
+        assert_ne!(before, permutation(&rng));
+        assert_ne!(rng.feistel_permute(u64::MAX, 64, 4), rng.feistel_permute(0, 64, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the domain")]
+    fn index_outside_domain() {
+        test_rng(3).feistel_permute(16, 4, 4);
+    }
+}
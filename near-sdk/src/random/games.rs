@@ -0,0 +1,133 @@
+use super::{Rng, SecureRng};
+use crate::{AccountId, env};
+
+impl SecureRng {
+    /// Eliminates a random share of `players` for the given `round` and returns the eliminated
+    /// players in random order. Survivors keep their relative order.
+    ///
+    /// The share starts at `base_elim_rate` in round zero and drops by `decay` every round,
+    /// clamped to `[0.0, 1.0]`, so later rounds eliminate fewer players. The number of
+    /// eliminations is the share of the pool rounded to the nearest integer, and at least one
+    /// player always survives.
+    ///
+    /// Panics if `base_elim_rate` or `decay` is not finite.
+    pub fn survival_round<T: Clone>(
+        &mut self,
+        players: &mut Vec<T>,
+        base_elim_rate: f64,
+        round: u32,
+        decay: f64,
+    ) -> Vec<T> {
+        if !base_elim_rate.is_finite() || !decay.is_finite() {
+            env::panic_str("survival_round: rates must be finite");
+        }
+        let rate = (base_elim_rate - decay * f64::from(round)).clamp(0.0, 1.0);
+        let count =
+            ((rate * players.len() as f64).round() as usize).min(players.len().saturating_sub(1));
+
+        let mut order: Vec<usize> = (0..players.len()).collect();
+        self.shuffle(&mut order);
+        let mut eliminated_flags = vec![false; players.len()];
+        let eliminated: Vec<T> = order[..count]
+            .iter()
+            .map(|&index| {
+                eliminated_flags[index] = true;
+                players[index].clone()
+            })
+            .collect();
+        let mut flags = eliminated_flags.into_iter();
+        players.retain(|_| !flags.next().unwrap_or_else(|| unreachable!()));
+        eliminated
+    }
+
+    /// Shuffles `players` and deals them into `team_count` teams whose sizes differ by at most
+    /// one. Returns no teams if `team_count` is zero.
+    pub fn balanced_teams<T: Clone>(&mut self, players: &[T], team_count: usize) -> Vec<Vec<T>> {
+        if team_count == 0 {
+            return Vec::new();
+        }
+        let mut order: Vec<usize> = (0..players.len()).collect();
+        self.shuffle(&mut order);
+        let mut teams = vec![Vec::with_capacity(players.len().div_ceil(team_count)); team_count];
+        for (seat, index) in order.into_iter().enumerate() {
+            teams[seat % team_count].push(players[index].clone());
+        }
+        teams
+    }
+
+    /// Returns the turn order of `players` for game `game_number` of a match series.
+    ///
+    /// Each game's order is derived from the generator's current state and `game_number`
+    /// without advancing the stream, so the orders of all games are fixed once the match
+    /// generator is, yet unrelated to each other. Keep the generator in contract state, or
+    /// recreate it from the same seed, to reproduce an order.
+    pub fn series_turn_order(&self, players: &[AccountId], game_number: u32) -> Vec<AccountId> {
+        let mut game = self.clone().split("series-turn-order", game_number.into());
+        let mut order = players.to_vec();
+        game.shuffle(&mut order);
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::test_rng;
+    use crate::test_utils::accounts;
+
+    #[test]
+    fn later_rounds_eliminate_fewer() {
+        let mut rng = test_rng(1);
+        let mut players: Vec<u32> = (0..100).collect();
+        let mut eliminated_per_round = Vec::new();
+        for round in 0..5 {
+            let before = players.clone();
+            let eliminated = rng.survival_round(&mut players, 0.5, round, 0.1);
+            assert_eq!(players.len() + eliminated.len(), before.len());
+            assert!(players.len() < before.len());
+            assert!(eliminated.iter().all(|player| !players.contains(player)));
+            assert!(players.windows(2).all(|pair| pair[0] < pair[1]));
+            eliminated_per_round.push(eliminated.len());
+        }
+        assert_eq!(eliminated_per_round, [50, 20, 9, 4, 2]);
+        assert!(rng.survival_round(&mut players, 0.5, 5, 0.1).is_empty());
+        assert_eq!(players.len(), 15);
+    }
+
+    #[test]
+    fn one_player_always_survives() {
+        let mut rng = test_rng(2);
+        let mut players = vec!["alice", "bob", "carol"];
+        assert_eq!(rng.survival_round(&mut players, 1.0, 0, 0.0).len(), 2);
+        assert_eq!(players.len(), 1);
+        assert!(rng.survival_round(&mut players, 1.0, 0, 0.0).is_empty());
+        assert!(rng.survival_round(&mut Vec::<u8>::new(), 1.0, 0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn teams_are_balanced() {
+        let mut rng = test_rng(3);
+        let players: Vec<u32> = (0..17).collect();
+        let teams = rng.balanced_teams(&players, 4);
+        assert_eq!(teams.iter().map(Vec::len).collect::<Vec<_>>(), [5, 4, 4, 4]);
+        let mut all = teams.concat();
+        all.sort_unstable();
+        assert_eq!(all, players);
+        assert_ne!(teams, rng.balanced_teams(&players, 4));
+
+        assert_eq!(rng.balanced_teams(&players[..1], 3), [vec![players[0]], vec![], vec![]]);
+        assert!(rng.balanced_teams(&players, 0).is_empty());
+    }
+
+    #[test]
+    fn turn_order_is_reproducible_per_game() {
+        let players: Vec<_> = (0..6).map(accounts).collect();
+        let series = test_rng(4);
+        let first = series.series_turn_order(&players, 1);
+        assert_eq!(first, series.series_turn_order(&players, 1));
+        assert_eq!(first, test_rng(4).series_turn_order(&players, 1));
+        assert_ne!(first, series.series_turn_order(&players, 2));
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, players);
+    }
+}
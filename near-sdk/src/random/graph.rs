@@ -0,0 +1,191 @@
+use super::{Rng, SecureRng};
+use crate::env;
+
+impl SecureRng {
+    /// Returns the edges of a random spanning tree over the complete graph on `nodes` nodes,
+    /// built with randomized Kruskal: every edge is visited in random order and kept unless it
+    /// would close a cycle.
+    ///
+    /// Each edge is returned as `(a, b)` with `a < b`. Fewer than two nodes produce no edges.
+    pub fn random_spanning_tree(&mut self, nodes: usize) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> =
+            (0..nodes).flat_map(|a| (a + 1..nodes).map(move |b| (a, b))).collect();
+        self.shuffle(&mut edges);
+
+        let mut components = DisjointSets::new(nodes);
+        let mut tree = Vec::with_capacity(nodes.saturating_sub(1));
+        for (a, b) in edges {
+            if tree.len() + 1 >= nodes {
+                break;
+            }
+            if components.union(a, b) {
+                tree.push((a, b));
+            }
+        }
+        tree
+    }
+
+    /// Returns the parent of every node in a random tree over `nodes` nodes rooted at node
+    /// zero, whose parent is `None`. Every other node gets a uniformly chosen parent among the
+    /// earlier nodes that have fewer than `max_children` children.
+    ///
+    /// Panics if `max_children` is zero and there is more than one node.
+    pub fn random_tree(&mut self, nodes: usize, max_children: usize) -> Vec<Option<usize>> {
+        if max_children == 0 && nodes > 1 {
+            env::panic_str("random_tree: max_children must be positive for more than one node");
+        }
+        let mut parents = Vec::with_capacity(nodes);
+        let mut children = vec![0; nodes];
+        // Earlier nodes that can still take a child; never empty since every node added
+        // brings `max_children` free slots and takes only one.
+        let mut open = Vec::new();
+        for node in 0..nodes {
+            if node == 0 {
+                parents.push(None);
+            } else {
+                let slot = self.usize(0..open.len());
+                let parent = open[slot];
+                children[parent] += 1;
+                if children[parent] == max_children {
+                    open.swap_remove(slot);
+                }
+                parents.push(Some(parent));
+            }
+            open.push(node);
+        }
+        parents
+    }
+
+    /// Walks up to `steps` edges from `start` and returns the visited nodes, starting with
+    /// `start`. `adjacency[node]` lists the `(neighbor, weight)` edges leaving `node`, and each
+    /// step follows one of them with probability proportional to its weight.
+    ///
+    /// The walk stops early at a node without positively weighted edges or outside
+    /// `adjacency`.
+    pub fn random_walk(
+        &mut self,
+        start: usize,
+        adjacency: &[Vec<(usize, u64)>],
+        steps: usize,
+    ) -> Vec<usize> {
+        let mut path = vec![start];
+        let mut node = start;
+        for _ in 0..steps {
+            let Some(edges) = adjacency.get(node) else { break };
+            let weights: Vec<u64> = edges.iter().map(|&(_, weight)| weight).collect();
+            let Some(&(next, _)) = self.choose_weighted(edges, &weights) else { break };
+            path.push(next);
+            node = next;
+        }
+        path
+    }
+}
+
+/// Union-find over `0..len` with path halving and union by size.
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self { parent: (0..len).collect(), size: vec![1; len] }
+    }
+
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    /// Merges the sets containing `a` and `b`, returning `false` if they were already merged.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisjointSets;
+    use crate::random::test_rng;
+
+    #[test]
+    fn spanning_tree_connects_all_nodes_without_cycles() {
+        let mut rng = test_rng(1);
+        for nodes in [2, 3, 10, 40] {
+            let tree = rng.random_spanning_tree(nodes);
+            assert_eq!(tree.len(), nodes - 1);
+            // A graph with `nodes - 1` edges is a tree iff adding them never closes a cycle.
+            let mut components = DisjointSets::new(nodes);
+            for &(a, b) in &tree {
+                assert!(a < b && b < nodes);
+                assert!(components.union(a, b), "cycle through {a}-{b}");
+            }
+            let root = components.find(0);
+            assert!((0..nodes).all(|node| components.find(node) == root));
+        }
+        assert!(rng.random_spanning_tree(0).is_empty());
+        assert!(rng.random_spanning_tree(1).is_empty());
+    }
+
+    #[test]
+    fn random_tree_respects_child_cap() {
+        let mut rng = test_rng(4);
+        for (nodes, max_children) in [(1, 0), (2, 1), (30, 1), (30, 2), (100, 3)] {
+            let parents = rng.random_tree(nodes, max_children);
+            assert_eq!(parents.len(), nodes);
+            assert_eq!(parents.iter().filter(|parent| parent.is_none()).count(), 1);
+            let mut children = vec![0; nodes];
+            for (node, parent) in parents.iter().enumerate().skip(1) {
+                // Parents always come earlier, so following them cannot cycle.
+                let parent = parent.unwrap();
+                assert!(parent < node);
+                children[parent] += 1;
+            }
+            assert!(children.iter().all(|&count| count <= max_children));
+        }
+        assert_eq!(rng.random_tree(4, 1), [None, Some(0), Some(1), Some(2)]);
+        assert!(rng.random_tree(0, 0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "max_children must be positive")]
+    fn random_tree_without_children() {
+        test_rng(5).random_tree(2, 0);
+    }
+
+    #[test]
+    fn random_walk_follows_edges() {
+        // 0 <-> 1 <-> 2 -> 3, where 3 is a dead end and 1 -> 0 is never taken.
+        let adjacency = vec![vec![(1, 1)], vec![(0, 0), (2, 1)], vec![(1, 3), (3, 1)], vec![]];
+        let walk = test_rng(6).random_walk(0, &adjacency, 50);
+        assert_eq!(walk[0], 0);
+        assert!(walk.len() <= 51);
+        for pair in walk.windows(2) {
+            assert!(adjacency[pair[0]].iter().any(|&(to, weight)| to == pair[1] && weight > 0));
+        }
+        if walk.len() < 51 {
+            assert_eq!(walk.last(), Some(&3));
+        }
+        assert_eq!(walk, test_rng(6).random_walk(0, &adjacency, 50));
+        assert_eq!(test_rng(7).random_walk(3, &adjacency, 5), [3]);
+        assert_eq!(test_rng(7).random_walk(9, &adjacency, 5), [9]);
+        assert_eq!(test_rng(7).random_walk(0, &adjacency, 0), [0]);
+    }
+
+    #[test]
+    fn spanning_tree_varies_by_seed() {
+        assert_ne!(test_rng(2).random_spanning_tree(12), test_rng(3).random_spanning_tree(12));
+    }
+}
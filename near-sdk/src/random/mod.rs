@@ -0,0 +1,1637 @@
+//! Pseudo-random number generation for smart contracts.
+//!
+//! [`SecureRng`] is a ChaCha20 stream seeded from the block's VRF output
+//! ([`env::random_seed_array`]) mixed with the context of the current transaction, so that
+//! different transactions in the same block observe different streams.
+//!
+//! Convenience methods for drawing bounded integers, floats, and picking or shuffling
+//! elements live on the [`Rng`] trait, which has to be in scope to be used.
+//!
+//! # Examples
+//!
+//! A fresh generator per call is enough for one-off draws:
+//!
+//! ```
+//! use near_sdk::random::{Rng, SecureRng};
+//! use near_sdk::{near, AccountId};
+//!
+//! #[near(contract_state)]
+//! #[derive(Default)]
+//! pub struct Lottery {
+//!     participants: Vec<AccountId>,
+//! }
+//!
+//! #[near]
+//! impl Lottery {
+//!     pub fn pick_winner(&self) -> Option<AccountId> {
+//!         let mut rng = SecureRng::new();
+//!         rng.choice(&self.participants).cloned()
+//!     }
+//! }
+//! ```
+//!
+//! [`SecureRng`] is Borsh-serializable, so it can also be kept in contract state to continue
+//! one stream across calls:
+//!
+//! ```
+//! use near_sdk::random::{Rng, SecureRng};
+//! use near_sdk::{near, AccountId, PanicOnDefault};
+//!
+//! #[near(contract_state)]
+//! #[derive(PanicOnDefault)]
+//! pub struct Raffle {
+//!     participants: Vec<AccountId>,
+//!     rng: SecureRng,
+//! }
+//!
+//! #[near]
+//! impl Raffle {
+//!     #[init]
+//!     pub fn new(participants: Vec<AccountId>) -> Self {
+//!         Self { participants, rng: SecureRng::new() }
+//!     }
+//!
+//!     pub fn draw(&mut self) -> Option<AccountId> {
+//!         self.rng.choice(&self.participants).cloned()
+//!     }
+//! }
+//!
+//! # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+//! let mut raffle = Raffle::new(vec!["alice.near".parse().unwrap(), "bob.near".parse().unwrap()]);
+//! let winner = raffle.draw().unwrap();
+//! assert!(raffle.participants.contains(&winner));
+//! ```
+//!
+//! # Gas
+//!
+//! Generators produce 64-byte ChaCha20 blocks of sixteen 32-bit words on demand, so the cost
+//! of a draw is mostly the share of a block it consumes, see [`SecureRng::estimate_bits`].
+//! `tests/random_gas_tests.rs` runs the methods below in a sandbox and fails if a call costs
+//! more than the ceiling listed here, excluding the cost of creating the generator.
+//!
+//! | Method                                    | Work                             | Ceiling  |
+//! |-------------------------------------------|----------------------------------|----------|
+//! | [`SecureRng::new`]                        | context host calls and a SHA-256 | 40 Ggas  |
+//! | [`Rng::u32`] and narrower integer draws   | one word                         | 1 Ggas   |
+//! | [`Rng::u64`], [`Rng::f64`]                | two words                        |          |
+//! | [`Rng::u128`]                             | four words                       |          |
+//! | [`Rng::shuffle`] of 1000 elements         | 999 one-word draws               | 400 Ggas |
+//! | [`Rng::sample_multiple`] of 10 in 1000    | 990 draws and a shuffle of 10    | 400 Ggas |
+//! | [`SecureRng::fork`]                       | a SHA-256                        |          |
+//! | [`SecureRng::split`]                      | eight words and a SHA-256        |          |
+//!
+//! The ceilings are conservative estimates that have not yet been calibrated against
+//! measured costs, so they only catch large regressions.
+//!
+//! # Security
+//!
+//! The block VRF output is known to the block producer before the block is published, so a
+//! validator can in theory predict (though not choose) the outcome of any draw. Contracts
+//! guarding high-value outcomes should combine it with a commit-reveal scheme.
+//!
+//! The transaction context mixed into the seed separates transactions from each other, but
+//! it is not secret: it only prevents two transactions, or two generators within one call,
+//! from observing the same stream.
+
+mod alias;
+pub use self::alias::WeightedSampler;
+mod allocation;
+mod audit;
+pub use self::audit::{AuditRecord, RngOp};
+mod builder;
+pub use self::builder::SecureRngBuilder;
+pub mod cards;
+mod commit_reveal;
+pub use self::commit_reveal::{CommitReveal, RevealError};
+mod distributions;
+pub use self::distributions::{Bernoulli, Distribution};
+#[cfg(feature = "fast-random")]
+mod fast;
+#[cfg(feature = "fast-random")]
+pub use self::fast::FastRng;
+mod feistel;
+mod games;
+mod graph;
+mod sampling;
+mod scheduler;
+mod shuffling;
+pub use self::scheduler::SmoothWeightedScheduler;
+
+/// `RandomVariant` generates a `random_variant(rng: &mut SecureRng) -> Self` constructor for
+/// enums with unit variants.
+///
+/// Variants are picked uniformly unless annotated with `#[weight(n)]`, in which case they are
+/// picked with probability proportional to `n`. Variants with a weight of zero are never
+/// picked.
+///
+/// ## Example
+/// ```rust
+/// use near_sdk::random::{RandomVariant, SecureRng};
+///
+/// #[derive(RandomVariant, Debug, PartialEq)]
+/// enum Prize {
+///     #[weight(90)]
+///     Common,
+///     #[weight(9)]
+///     Rare,
+///     #[weight(1)]
+///     Legendary,
+/// }
+///
+/// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+/// let mut rng = SecureRng::new();
+/// let prize = Prize::random_variant(&mut rng);
+/// ```
+pub use near_sdk_macros::RandomVariant;
+
+#[cfg(feature = "abi")]
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range, RangeBounds};
+use std::{fmt, io};
+
+#[cfg(feature = "abi")]
+use borsh::BorshSchema;
+use borsh::{BorshDeserialize, BorshSerialize};
+pub use rand::RngCore;
+use rand::SeedableRng;
+use rand::distributions::uniform::SampleUniform;
+use rand_chacha::ChaCha20Rng;
+
+use crate::json_types::{U64, U128};
+use crate::{AccountId, env};
+
+/// Domain tag prepended to game ids so their seeds never collide with other entropy inputs.
+const GAME_ID_DOMAIN: &[u8] = b"near-sdk:random:game-id:";
+/// Domain tag prepended to token ids by [`SecureRng::for_token`].
+const TOKEN_DOMAIN: &[u8] = b"near-sdk:random:token:";
+/// Domain tag for child streams derived with [`SecureRng::split`].
+const SPLIT_DOMAIN: &[u8] = b"near-sdk:random:split:";
+/// Domain tag for child streams derived with [`SecureRng::fork`].
+const FORK_DOMAIN: &[u8] = b"near-sdk:random:fork:";
+/// Domain tag appended to external data folded in by [`SecureRng::mix`].
+const MIX_DOMAIN: &[u8] = b"near-sdk:random:mix:";
+/// Domain tag for generators derived with [`SecureRng::blend_oracle`].
+const ORACLE_DOMAIN: &[u8] = b"near-sdk:random:oracle:";
+
+/// Cryptographically secure pseudo-random number generator seeded from block entropy.
+///
+/// See the [module-level documentation](self) for how the seed is derived.
+///
+/// The generator is Borsh-serializable as its seed and position in the stream, so it can be
+/// kept in contract state and resume the same stream in later calls.
+#[derive(Clone)]
+pub struct SecureRng {
+    inner: ChaCha20Rng,
+}
+
+impl SecureRng {
+    /// Creates a generator seeded from the block random seed and the current transaction
+    /// context (current and predecessor accounts, signer public key, block height, prepaid
+    /// gas and gas used so far). Generators created one after another in the same call
+    /// therefore produce different streams.
+    ///
+    /// Use [`SecureRngBuilder`] to mix in further inputs, such as a nonce or a promise result.
+    pub fn new() -> Self {
+        SecureRngBuilder::new().with_block_seed().with_account_context().build()
+    }
+
+    /// Creates a generator seeded from the block random seed mixed with `extra` entropy,
+    /// for example a value committed by a user.
+    ///
+    /// This is a shorthand for `SecureRngBuilder::new().with_block_seed().with_bytes(extra)`.
+    pub fn with_entropy(extra: &[u8]) -> Self {
+        SecureRngBuilder::new().with_block_seed().with_bytes(extra).build()
+    }
+
+    /// Creates a generator for the game identified by `game_id`, mixed with the block random
+    /// seed so that the layout cannot be known before the game is created.
+    ///
+    /// Store the block random seed alongside the game if the layout has to be recomputed
+    /// later, or use [`from_game_id_deterministic`](Self::from_game_id_deterministic) for
+    /// layouts that anyone should be able to reproduce from the id alone.
+    pub fn from_game_id(game_id: &str) -> Self {
+        Self::with_entropy(&[GAME_ID_DOMAIN, game_id.as_bytes()].concat())
+    }
+
+    /// Creates a generator derived only from `game_id`, so the same id always produces the
+    /// same stream. Useful for shareable puzzles, but the outcome is public to anyone who
+    /// knows the id and must not decide anything of value.
+    pub fn from_game_id_deterministic(game_id: &str) -> Self {
+        Self::from_entropy(&[GAME_ID_DOMAIN, game_id.as_bytes()].concat())
+    }
+
+    /// Creates a generator for fixing the traits of `token_id` at mint time, derived from the
+    /// token id and the block random seed.
+    ///
+    /// Store [`env::random_seed_array`] with the token so that anyone can later reproduce the
+    /// traits with [`for_token_with_block_seed`](Self::for_token_with_block_seed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use near_sdk::random::{RandomVariant, SecureRng};
+    ///
+    /// #[derive(RandomVariant, Debug, PartialEq)]
+    /// enum Background {
+    ///     #[weight(70)]
+    ///     Plain,
+    ///     #[weight(25)]
+    ///     Gold,
+    ///     #[weight(5)]
+    ///     Cosmic,
+    /// }
+    ///
+    /// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+    /// let block_seed = near_sdk::env::random_seed_array();
+    /// let background = Background::random_variant(&mut SecureRng::for_token("token-1"));
+    ///
+    /// // Later, from the stored block seed:
+    /// let mut replay = SecureRng::for_token_with_block_seed("token-1", block_seed);
+    /// assert_eq!(Background::random_variant(&mut replay), background);
+    /// ```
+    pub fn for_token(token_id: &str) -> Self {
+        Self::for_token_with_block_seed(token_id, env::random_seed_array())
+    }
+
+    /// Recreates the generator [`for_token`](Self::for_token) returned for `token_id` in the
+    /// block whose random seed was `block_seed`.
+    pub fn for_token_with_block_seed(token_id: &str, block_seed: [u8; 32]) -> Self {
+        Self::from_entropy(&[&block_seed, TOKEN_DOMAIN, token_id.as_bytes()].concat())
+    }
+
+    /// Creates a generator for a view method from a seed supplied by the caller, for example
+    /// as a method argument or read from contract state.
+    ///
+    /// Views are not part of consensus and the block random seed means nothing there, so this
+    /// is the sanctioned way to produce "random" output in a view: it makes no environment
+    /// calls and the same seed always reproduces the same stream. The output is only as
+    /// unpredictable as the seed and must never decide anything of value.
+    pub fn for_view(explicit_seed: [u8; 32]) -> Self {
+        Self::from_seed(explicit_seed)
+    }
+
+    /// Creates a generator seeded directly with `seed`, without any environment calls, so that
+    /// the same seed always reproduces the same stream. Useful for commit-reveal schemes, where
+    /// the seed is revealed after the fact, and for byte-exact unit tests.
+    ///
+    /// The block random seed is not mixed in: the output is only as unpredictable as `seed`,
+    /// which must stay secret until the outcome no longer matters.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self { inner: ChaCha20Rng::from_seed(seed) }
+    }
+
+    /// Returns the 32-byte seed of the current stream, which is the seed derived at
+    /// construction or by the last [`reseed`](Self::reseed).
+    ///
+    /// Publishing it lets anyone replay the stream from the start with
+    /// [`from_seed`](Self::from_seed), for example to prove the fairness of a finished game.
+    pub fn seed(&self) -> [u8; 32] {
+        self.inner.get_seed()
+    }
+
+    /// Derives an independent child generator addressed by `domain` and `index`, for example
+    /// `split("combat", round)`.
+    ///
+    /// The child is keyed by the next 32 bytes of this stream, so the same parent state and
+    /// the same `(domain, index)` pair always produce the same child, while different pairs
+    /// produce unrelated streams.
+    pub fn split(&mut self, domain: &str, index: u64) -> SecureRng {
+        let mut key = [0u8; 32];
+        self.inner.fill_bytes(&mut key);
+        Self::from_entropy(&[SPLIT_DOMAIN, &key, domain.as_bytes(), &index.to_le_bytes()].concat())
+    }
+
+    /// Derives a child generator from the current seed and position of this generator and
+    /// `label`, without advancing this stream or reading the environment.
+    ///
+    /// Forks are much cheaper than [`new`](Self::new) for running several independent random
+    /// processes in one call: forks with different labels are unrelated, while the same label
+    /// from the same parent state always produces the same child. Unlike
+    /// [`split`](Self::split), the parent stream is unchanged, so use distinct labels for
+    /// forks taken without drawing from the parent in between.
+    pub fn fork(&self, label: &[u8]) -> SecureRng {
+        Self::from_entropy(
+            &[FORK_DOMAIN, &self.inner.get_seed(), &self.inner.get_word_pos().to_le_bytes(), label]
+                .concat(),
+        )
+    }
+
+    /// Derives a generator from the next 32 bytes of this stream mixed with `oracle_value`,
+    /// for example a number published by an external randomness oracle.
+    ///
+    /// The block producer cannot predict the oracle value and the oracle cannot predict the
+    /// stream, so neither controls the result alone.
+    pub fn blend_oracle(&mut self, oracle_value: u64) -> Self {
+        let mut key = [0u8; 32];
+        self.inner.fill_bytes(&mut key);
+        Self::from_entropy(&[ORACLE_DOMAIN, &key, &oracle_value.to_le_bytes()].concat())
+    }
+
+    /// Returns an array of `N` random bytes, for example a salt, nonce or 32-byte key.
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0; N];
+        self.inner.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    /// Returns `n` random bytes.
+    pub fn bytes_vec(&mut self, n: usize) -> Vec<u8> {
+        let mut bytes = vec![0; n];
+        self.inner.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    /// Returns `byte_len` random bytes encoded as lowercase hex, `2 * byte_len` characters
+    /// long, for example as a unique token or room id.
+    pub fn hex_string(&mut self, byte_len: usize) -> String {
+        self.bytes_vec(byte_len).iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Returns `byte_len` random bytes encoded as base58, the encoding NEAR uses for keys and
+    /// hashes. The length of the result varies with the leading bytes.
+    pub fn base58_string(&mut self, byte_len: usize) -> String {
+        bs58::encode(self.bytes_vec(byte_len)).into_string()
+    }
+
+    /// Returns a random valid account id made of up to 12 lowercase alphanumeric characters,
+    /// followed by `.suffix` unless `suffix` is empty, for example `a8f3k2x0q9zt.testnet`.
+    ///
+    /// The random part is shortened as needed to keep the id within 64 bytes. Meant for test
+    /// fixtures that need many distinct accounts.
+    ///
+    /// Panics if `suffix` is not a valid account id or leaves no room for the random part.
+    pub fn account_id(&mut self, suffix: &str) -> AccountId {
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        let len = if suffix.is_empty() { 12 } else { 12.min(63usize.saturating_sub(suffix.len())) };
+        let mut id: String =
+            (0..len).map(|_| char::from(ALPHABET[self.usize(0..ALPHABET.len())])).collect();
+        if !suffix.is_empty() {
+            id.push('.');
+            id.push_str(suffix);
+        }
+        id.parse().unwrap_or_else(|_| env::panic_str("account_id: invalid suffix"))
+    }
+
+    /// Returns the value the next [`next_u32`](RngCore::next_u32) call will return, without
+    /// advancing the stream.
+    pub fn peek_u32(&self) -> u32 {
+        self.inner.clone().next_u32()
+    }
+
+    /// Returns how many 32-bit words remain before the ChaCha20 stream, whose period is
+    /// `2^68` words, wraps around.
+    ///
+    /// This is informational only: no contract can realistically exhaust the stream.
+    pub fn stream_remaining_words(&self) -> u128 {
+        (1u128 << 68) - self.inner.get_word_pos()
+    }
+
+    /// Replaces the stream with a new one derived from the current transaction context and
+    /// the output of the current stream.
+    pub fn reseed(&mut self) {
+        let carry = self.inner.next_u64();
+        *self = SecureRngBuilder::new()
+            .with_block_seed()
+            .with_account_context()
+            .with_u64(carry)
+            .build();
+    }
+
+    /// Folds external `data`, such as an oracle's VRF output received in a callback, into the
+    /// generator by replacing its seed with `sha256(seed || data || domain)` and starting over
+    /// on the new stream.
+    ///
+    /// Mixing is one-way: the previous stream cannot be recovered from the new seed. It is
+    /// also auditable, since anyone who knows the previous [`seed`](Self::seed) and `data` can
+    /// recompute the new seed. The position in the current stream is not part of the input,
+    /// so mixing the same data into the same seed always yields the same stream.
+    pub fn mix(&mut self, data: &[u8]) {
+        *self = Self::from_entropy(&[&self.inner.get_seed(), data, MIX_DOMAIN].concat());
+    }
+
+    fn from_entropy(entropy: &[u8]) -> Self {
+        Self { inner: ChaCha20Rng::from_seed(env::sha256_array(entropy)) }
+    }
+}
+
+impl Default for SecureRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generators are equal when they share a seed and position, so that they will produce the
+/// same draws.
+impl PartialEq for SecureRng {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.get_seed() == other.inner.get_seed()
+            && self.inner.get_word_pos() == other.inner.get_word_pos()
+    }
+}
+
+impl Eq for SecureRng {}
+
+/// Shows the seed and position in the stream, the same state that is serialized, rather than
+/// the buffered keystream.
+impl fmt::Debug for SecureRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureRng")
+            .field("seed", &self.inner.get_seed())
+            .field("word_pos", &self.inner.get_word_pos())
+            .finish()
+    }
+}
+
+impl RngCore for SecureRng {
+    fn next_u32(&mut self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+impl BorshSerialize for SecureRng {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.inner.get_seed(), self.inner.get_word_pos()).serialize(writer)
+    }
+}
+
+impl BorshDeserialize for SecureRng {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let (seed, word_pos) = <([u8; 32], u128)>::deserialize_reader(reader)?;
+        let mut inner = ChaCha20Rng::from_seed(seed);
+        inner.set_word_pos(word_pos);
+        Ok(Self { inner })
+    }
+}
+
+/// Described as the `(seed, word_pos)` tuple it is serialized as.
+#[cfg(feature = "abi")]
+impl BorshSchema for SecureRng {
+    fn add_definitions_recursively(
+        definitions: &mut BTreeMap<borsh::schema::Declaration, borsh::schema::Definition>,
+    ) {
+        <([u8; 32], u128)>::add_definitions_recursively(definitions);
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        <([u8; 32], u128)>::declaration()
+    }
+}
+
+/// Integer types accepted by the range methods of [`Rng`].
+trait RangeInt: Copy + PartialOrd + SampleUniform + fmt::Debug {
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_succ(self) -> Option<Self>;
+
+    fn checked_pred(self) -> Option<Self>;
+}
+
+macro_rules! impl_range_int {
+    ($($ty:ty),*) => {$(
+        impl RangeInt for $ty {
+            const MIN: Self = <$ty>::MIN;
+            const MAX: Self = <$ty>::MAX;
+
+            fn checked_succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn checked_pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+        }
+    )*};
+}
+
+impl_range_int!(u8, u16, u32, u64, u128, usize, i32, i64, i128);
+
+/// Draws a uniformly distributed value from `range`. The range is converted to an inclusive one
+/// first, so that bounds at the edges of `T` never overflow.
+fn gen_in<R: RngCore + ?Sized, T: RangeInt>(rng: &mut R, range: impl RangeBounds<T>) -> T {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => Some(start),
+        Bound::Excluded(&start) => start.checked_succ(),
+        Bound::Unbounded => Some(T::MIN),
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => Some(end),
+        Bound::Excluded(&end) => end.checked_pred(),
+        Bound::Unbounded => Some(T::MAX),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => rand::Rng::gen_range(rng, start..=end),
+        _ => env::panic_str(&format!("SecureRng: empty range {} requested", describe(&range))),
+    }
+}
+
+/// Formats `range` as it would be written in Rust, for panic messages.
+fn describe<T: fmt::Debug>(range: &impl RangeBounds<T>) -> String {
+    let start = match range.start_bound() {
+        Bound::Included(start) => format!("{start:?}"),
+        Bound::Excluded(start) => format!("{start:?} (excluded)"),
+        Bound::Unbounded => String::new(),
+    };
+    match range.end_bound() {
+        Bound::Included(end) => format!("{start}..={end:?}"),
+        Bound::Excluded(end) => format!("{start}..{end:?}"),
+        Bound::Unbounded => format!("{start}.."),
+    }
+}
+
+/// Convenience methods for drawing values from a random number generator.
+///
+/// All methods have default implementations on top of [`RngCore`].
+pub trait Rng: RngCore {
+    /// Returns a uniformly distributed `u8` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn u8(&mut self, range: impl RangeBounds<u8>) -> u8 {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `u16` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn u16(&mut self, range: impl RangeBounds<u16>) -> u16 {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `u32` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use near_sdk::random::{Rng, SecureRng};
+    ///
+    /// # near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+    /// let mut rng = SecureRng::new();
+    /// let score = rng.u32(1..=100);
+    /// assert!((1..=100).contains(&score));
+    ///
+    /// // `..` draws across the whole width of the type, for example for raw ids.
+    /// let id: u32 = rng.u32(..);
+    /// # let _ = id;
+    /// ```
+    fn u32(&mut self, range: impl RangeBounds<u32>) -> u32 {
+        gen_in(self, range)
+    }
+
+    /// Fills `out` with independent uniformly distributed draws from `range`.
+    ///
+    /// Panics if `range` is empty and `out` is not.
+    fn fill_u32(&mut self, range: impl RangeBounds<u32> + Clone, out: &mut [u32]) {
+        for value in out {
+            *value = self.u32(range.clone());
+        }
+    }
+
+    /// Returns `n` independent uniformly distributed draws from `range`.
+    ///
+    /// Panics if `range` is empty and `n` is not zero.
+    fn gen_vec_u32(&mut self, range: impl RangeBounds<u32> + Clone, n: usize) -> Vec<u32> {
+        let mut values = vec![0; n];
+        self.fill_u32(range, &mut values);
+        values
+    }
+
+    /// Returns a uniformly distributed `u64` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn u64(&mut self, range: impl RangeBounds<u64>) -> u64 {
+        gen_in(self, range)
+    }
+
+    /// Like [`u64`](Self::u64), but wrapped in [`U64`] so that the value serializes to JSON as
+    /// a string and keeps its precision in JavaScript clients.
+    fn u64_json(&mut self, range: impl RangeBounds<u64>) -> U64 {
+        self.u64(range).into()
+    }
+
+    /// Returns a uniformly distributed `u128` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn u128(&mut self, range: impl RangeBounds<u128>) -> u128 {
+        gen_in(self, range)
+    }
+
+    /// Like [`u128`](Self::u128), but wrapped in [`U128`] so that the value serializes to JSON
+    /// as a string.
+    fn u128_json(&mut self, range: impl RangeBounds<u128>) -> U128 {
+        self.u128(range).into()
+    }
+
+    /// Returns a uniformly distributed `usize` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn usize(&mut self, range: impl RangeBounds<usize>) -> usize {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `i32` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn i32(&mut self, range: impl RangeBounds<i32>) -> i32 {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `i64` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn i64(&mut self, range: impl RangeBounds<i64>) -> i64 {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `i128` in `range`, which may take any form such as
+    /// `a..b`, `a..=b`, `a..` or `..`.
+    ///
+    /// Panics if `range` is empty.
+    fn i128(&mut self, range: impl RangeBounds<i128>) -> i128 {
+        gen_in(self, range)
+    }
+
+    /// Returns a uniformly distributed `f64` in `[0.0, 1.0)`.
+    fn f64(&mut self) -> f64 {
+        rand::Rng::r#gen(self)
+    }
+
+    /// Returns a uniformly distributed `f64` in `range`, such as a price or an angle.
+    ///
+    /// Panics if `range` is empty or either bound is not finite.
+    fn f64_range(&mut self, range: Range<f64>) -> f64 {
+        if !range.start.is_finite() || !range.end.is_finite() || range.start >= range.end {
+            env::panic_str(&format!("SecureRng: empty or non-finite range {range:?} requested"));
+        }
+        let u = self.f64();
+        // Interpolating between the bounds cannot overflow, unlike scaling by their distance.
+        let value = range.start * (1.0 - u) + range.end * u;
+        if range.contains(&value) { value } else { range.start }
+    }
+
+    /// Returns a uniformly distributed `f32` in `range`.
+    ///
+    /// Panics if `range` is empty or either bound is not finite.
+    fn f32_range(&mut self, range: Range<f32>) -> f32 {
+        if !range.start.is_finite() || !range.end.is_finite() || range.start >= range.end {
+            env::panic_str(&format!("SecureRng: empty or non-finite range {range:?} requested"));
+        }
+        let u: f32 = rand::Rng::r#gen(self);
+        let value = range.start * (1.0 - u) + range.end * u;
+        if range.contains(&value) { value } else { range.start }
+    }
+
+    /// Returns `true` or `false` with equal probability.
+    fn flip_coin(&mut self) -> bool {
+        rand::Rng::r#gen(self)
+    }
+
+    /// Returns `true` with the given `probability`, clamped to `[0.0, 1.0]`, using a single
+    /// draw. A NaN probability never succeeds.
+    fn chance(&mut self, probability: f64) -> bool {
+        self.f64() < probability.clamp(0.0, 1.0)
+    }
+
+    /// Rolls a die with `sides` faces, returning a value in `1..=sides`.
+    ///
+    /// Panics if `sides` is zero.
+    fn roll_die(&mut self, sides: u8) -> u8 {
+        self.u8(1..=sides)
+    }
+
+    /// Rolls `count` dice with `sides` faces each and returns the sum, for example
+    /// `roll_dice(3, 6)` for "3d6".
+    ///
+    /// Returns zero if `count` or `sides` is zero.
+    fn roll_dice(&mut self, count: u8, sides: u8) -> u32 {
+        self.roll_dice_vec(count, sides).into_iter().map(u32::from).sum()
+    }
+
+    /// Rolls `count` dice with `sides` faces each and returns the individual results.
+    ///
+    /// Returns no results if `count` or `sides` is zero.
+    fn roll_dice_vec(&mut self, count: u8, sides: u8) -> Vec<u8> {
+        if sides == 0 {
+            return Vec::new();
+        }
+        (0..count).map(|_| self.roll_die(sides)).collect()
+    }
+
+    /// Rolls a die with `sides` faces twice and returns the higher result, as when rolling
+    /// with advantage.
+    ///
+    /// Panics if `sides` is zero.
+    fn roll_advantage(&mut self, sides: u8) -> u8 {
+        self.roll_die(sides).max(self.roll_die(sides))
+    }
+
+    /// Rolls a die with `sides` faces twice and returns the lower result, as when rolling
+    /// with disadvantage.
+    ///
+    /// Panics if `sides` is zero.
+    fn roll_disadvantage(&mut self, sides: u8) -> u8 {
+        self.roll_die(sides).min(self.roll_die(sides))
+    }
+
+    /// Rolls `count` dice with `sides` faces each and returns the sum of the `keep` highest,
+    /// for example `roll_keep_highest(4, 6, 3)` for "4d6 drop lowest".
+    ///
+    /// Keeps all dice if `keep` exceeds `count`, and returns zero if `count` or `sides` is
+    /// zero.
+    fn roll_keep_highest(&mut self, count: u8, sides: u8, keep: u8) -> u32 {
+        let mut rolls = self.roll_dice_vec(count, sides);
+        rolls.sort_unstable_by(|a, b| b.cmp(a));
+        rolls.into_iter().take(usize::from(keep)).map(u32::from).sum()
+    }
+
+    /// Returns a uniformly distributed percentage in `0..=100`.
+    fn percentage(&mut self) -> u8 {
+        rand::Rng::gen_range(self, 0..=100)
+    }
+
+    /// Returns a uniformly distributed number of basis points in `0..=10_000`, for odds finer
+    /// than a [`percentage`](Self::percentage) without floating point.
+    fn basis_points(&mut self) -> u16 {
+        rand::Rng::gen_range(self, 0..=10_000)
+    }
+
+    /// Returns a uniformly distributed number of thousandths in `0..=1_000`.
+    fn per_mille(&mut self) -> u16 {
+        rand::Rng::gen_range(self, 0..=1_000)
+    }
+
+    /// Returns a uniformly chosen element of `slice`, or `None` if it is empty.
+    fn choice<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        self.choice_index(slice).map(|index| &slice[index])
+    }
+
+    /// Returns the index of a uniformly chosen element of `slice`, or `None` if it is empty.
+    ///
+    /// Useful when the chosen element has to be removed or updated afterwards.
+    fn choice_index<T>(&mut self, slice: &[T]) -> Option<usize> {
+        if slice.is_empty() {
+            return None;
+        }
+        Some(self.usize(0..slice.len()))
+    }
+
+    /// Returns a mutable reference to a uniformly chosen element of `slice`, or `None` if it
+    /// is empty.
+    fn choice_mut<'a, T>(&mut self, slice: &'a mut [T]) -> Option<&'a mut T> {
+        self.choice_index(slice).map(|index| &mut slice[index])
+    }
+
+    /// Returns a uniformly chosen element of `slice` among those matching `pred`, or `None` if
+    /// none does.
+    ///
+    /// Uses single-element reservoir sampling, so the slice is traversed once without
+    /// collecting the matching elements, at the cost of one draw per match.
+    fn choice_where<'a, T, F: Fn(&T) -> bool>(&mut self, slice: &'a [T], pred: F) -> Option<&'a T> {
+        let mut chosen = None;
+        for (seen, item) in slice.iter().filter(|item| pred(item)).enumerate() {
+            if self.usize(0..=seen) == 0 {
+                chosen = Some(item);
+            }
+        }
+        chosen
+    }
+
+    /// Returns an element of `items` chosen with probability proportional to its weight in
+    /// `weights`, using a single draw over the cumulative weights.
+    ///
+    /// Returns `None` if the slices differ in length or no weight is positive.
+    fn choose_weighted<'a, T>(&mut self, items: &'a [T], weights: &[u64]) -> Option<&'a T> {
+        if items.len() != weights.len() {
+            return None;
+        }
+        let cumulative = sampling::cumulative_weights(weights);
+        let total = cumulative.last().copied().filter(|&total| total > 0)?;
+        Some(&items[sampling::index_for(&cumulative, self.u128(0..total))])
+    }
+
+    /// Shuffles `slice` in place using the Fisher-Yates algorithm.
+    fn shuffle<T>(&mut self, slice: &mut [T]) {
+        rand::seq::SliceRandom::shuffle(slice, self)
+    }
+
+    /// Moves a uniformly random sample of `k` elements, in random order, to the front of
+    /// `slice` using a partial Fisher-Yates shuffle. The order of the remaining elements is
+    /// unspecified. `k` is clamped to the length of the slice.
+    ///
+    /// Makes `k` draws instead of the `slice.len() - 1` a full [`shuffle`](Self::shuffle) needs.
+    fn shuffle_partial<T>(&mut self, slice: &mut [T], k: usize) {
+        for i in 0..k.min(slice.len().saturating_sub(1)) {
+            let j = self.usize(i..slice.len());
+            slice.swap(i, j);
+        }
+    }
+
+    /// Returns `count` distinct elements of `slice` in random order.
+    ///
+    /// `count` saturates at the length of the slice: any larger value, up to `usize::MAX`,
+    /// returns every element in its original order without drawing.
+    ///
+    /// Uses reservoir sampling, so only `count` elements are allocated and the slice is
+    /// traversed once.
+    fn sample_multiple<'a, T>(&mut self, slice: &'a [T], count: usize) -> Vec<&'a T> {
+        let count = count.min(slice.len());
+        if count == slice.len() {
+            return slice.iter().collect();
+        }
+        let mut reservoir: Vec<&T> = slice[..count].iter().collect();
+        for (seen, item) in slice.iter().enumerate().skip(count) {
+            let replaced = self.usize(0..=seen);
+            if replaced < count {
+                reservoir[replaced] = item;
+            }
+        }
+        // The reservoir is a uniform subset, but later elements tend to land in given slots.
+        self.shuffle(&mut reservoir);
+        reservoir
+    }
+
+    /// Returns `count` distinct elements of `slice` in their original relative order, or all
+    /// of them if `count` is not smaller than the length of the slice.
+    ///
+    /// Useful when the subset is shown to users, such as committee members listed in the
+    /// order they registered.
+    fn sample_ordered<'a, T>(&mut self, slice: &'a [T], count: usize) -> Vec<&'a T> {
+        let mut indices =
+            rand::seq::index::sample(self, slice.len(), count.min(slice.len())).into_vec();
+        indices.sort_unstable();
+        indices.into_iter().map(|index| &slice[index]).collect()
+    }
+}
+
+impl Rng for SecureRng {}
+
+/// Sets up a testing context whose block random seed is `[seed; 32]` and returns a generator
+/// created in it with [`SecureRng::new`].
+#[cfg(test)]
+pub(crate) fn test_rng(seed: u8) -> SecureRng {
+    crate::testing_env!(crate::test_utils::VMContextBuilder::new().random_seed([seed; 32]).build());
+    SecureRng::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{VMContextBuilder, accounts};
+
+    fn setup(seed: [u8; 32]) {
+        crate::testing_env!(VMContextBuilder::new().random_seed(seed).build());
+    }
+
+    #[test]
+    fn same_context_same_stream() {
+        setup([1; 32]);
+        let a = SecureRng::new().next_u64();
+        setup([1; 32]);
+        assert_eq!(a, SecureRng::new().next_u64());
+    }
+
+    #[test]
+    fn consecutive_generators_in_one_call_diverge() {
+        setup([1; 32]);
+        let mut a = SecureRng::new();
+        let mut b = SecureRng::new();
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn different_seed_different_stream() {
+        setup([1; 32]);
+        let a = SecureRng::new().next_u64();
+        setup([2; 32]);
+        let b = SecureRng::new().next_u64();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn different_predecessor_different_stream() {
+        crate::testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).build());
+        let a = SecureRng::new().next_u64();
+        crate::testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(2)).build());
+        let b = SecureRng::new().next_u64();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn different_block_height_different_stream() {
+        crate::testing_env!(VMContextBuilder::new().block_height(10).build());
+        let a = SecureRng::new().next_u64();
+        crate::testing_env!(VMContextBuilder::new().block_height(11).build());
+        let b = SecureRng::new().next_u64();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn with_entropy_differs_from_new() {
+        setup([3; 32]);
+        let a = SecureRng::new().next_u64();
+        let b = SecureRng::with_entropy(b"commitment").next_u64();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn reseed_changes_stream() {
+        let mut rng = test_rng(4);
+        let mut reseeded = rng.clone();
+        reseeded.reseed();
+        assert_ne!(rng.next_u64(), reseeded.next_u64());
+        assert_ne!(rng.seed(), reseeded.seed());
+        let mut replay = SecureRng::from_seed(reseeded.seed());
+        replay.next_u64();
+        assert_eq!(replay.next_u64(), reseeded.next_u64());
+    }
+
+    #[test]
+    fn view_rng_reproduces_stream_from_explicit_seed() {
+        // No `testing_env!`: the generator must not depend on the VM context.
+        let mut a = SecureRng::for_view([5; 32]);
+        let mut b = SecureRng::for_view([5; 32]);
+        let mut c = SecureRng::for_view([6; 32]);
+        let a: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(a, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(a, (0..8).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn same_explicit_seed_same_stream() {
+        let mut a = SecureRng::from_seed([11; 32]);
+        let mut b = SecureRng::from_seed([11; 32]);
+        let mut c = SecureRng::from_seed([12; 32]);
+        let a: Vec<u32> = (0..16).map(|_| a.next_u32()).collect();
+        assert_eq!(a, (0..16).map(|_| b.next_u32()).collect::<Vec<_>>());
+        assert_ne!(a, (0..16).map(|_| c.next_u32()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn peek_does_not_advance_stream() {
+        let mut rng = SecureRng::from_seed([13; 32]);
+        rng.next_u64();
+        let peeked = rng.peek_u32();
+        assert_eq!(rng.peek_u32(), peeked);
+        assert_eq!(rng.next_u32(), peeked);
+        assert_ne!(rng.peek_u32(), peeked);
+    }
+
+    #[test]
+    fn encoded_ids_have_expected_alphabet() {
+        let mut rng = SecureRng::from_seed([14; 32]);
+        let id = rng.hex_string(16);
+        assert_eq!(id.len(), 32);
+        assert!(id.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')), "{id}");
+        assert_ne!(rng.hex_string(16), id);
+        assert_eq!(rng.hex_string(0), "");
+
+        let id = rng.base58_string(32);
+        assert!((40..=44).contains(&id.len()), "{id}");
+        assert!(id.chars().all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c)), "{id}");
+        assert_ne!(rng.base58_string(32), id);
+        assert_eq!(rng.base58_string(0), "");
+    }
+
+    #[test]
+    fn forks_are_labelled_substreams() {
+        setup([23; 32]);
+        let mut parent = SecureRng::new();
+        let before = parent.peek_u32();
+        let mut combat = parent.fork(b"combat");
+        let mut loot = parent.fork(b"loot");
+        assert_eq!(parent.peek_u32(), before);
+
+        let combat_draws: Vec<u64> = (0..4).map(|_| combat.next_u64()).collect();
+        assert_ne!(combat_draws, (0..4).map(|_| loot.next_u64()).collect::<Vec<_>>());
+        let mut again = parent.fork(b"combat");
+        assert_eq!(combat_draws, (0..4).map(|_| again.next_u64()).collect::<Vec<_>>());
+
+        let mut before_draws = parent.fork(b"combat");
+        parent.next_u32();
+        assert_eq!(before_draws.next_u64(), combat_draws[0]);
+        assert_ne!(parent.fork(b"combat").next_u64(), combat_draws[0]);
+    }
+
+    #[test]
+    fn mixing_is_deterministic_per_data() {
+        let mixed = |data: &[u8]| {
+            let mut rng = SecureRng::from_seed([31; 32]);
+            rng.mix(data);
+            rng
+        };
+        let mut vrf = mixed(b"oracle-vrf-output");
+        let mut again = mixed(b"oracle-vrf-output");
+        let mut other = mixed(b"oracle-vrf-outpuu");
+        assert_eq!(vrf.seed(), again.seed());
+        assert_eq!(vrf.next_u64(), again.next_u64());
+        assert_ne!(vrf.seed(), other.seed());
+        assert_ne!(vrf.next_u64(), other.next_u64());
+
+        let expected =
+            env::sha256_array([&[31; 32][..], b"oracle-vrf-output", MIX_DOMAIN].concat());
+        assert_eq!(mixed(b"oracle-vrf-output").seed(), expected);
+        assert_eq!(mixed(b"oracle-vrf-output").stream_remaining_words(), 1 << 68);
+        assert_ne!(mixed(b"").seed(), [31; 32]);
+    }
+
+    #[test]
+    fn published_seed_replays_stream_from_start() {
+        let mut rng = test_rng(12);
+        let drawn: Vec<u32> = (0..16).map(|_| rng.next_u32()).collect();
+        let mut replay = SecureRng::from_seed(rng.seed());
+        assert_eq!(drawn, (0..16).map(|_| replay.next_u32()).collect::<Vec<_>>());
+        assert_eq!(SecureRng::from_seed([11; 32]).seed(), [11; 32]);
+    }
+
+    #[test]
+    fn oracle_and_block_entropy_both_contribute() {
+        setup([13; 32]);
+        let rng = SecureRng::new();
+        let a = rng.clone().blend_oracle(1).next_u64();
+        assert_eq!(a, rng.clone().blend_oracle(1).next_u64());
+        assert_ne!(a, rng.clone().blend_oracle(2).next_u64());
+
+        setup([14; 32]);
+        assert_ne!(a, SecureRng::new().blend_oracle(1).next_u64());
+    }
+
+    #[test]
+    fn random_bytes() {
+        let mut rng = test_rng(17);
+        let first: [u8; 32] = rng.bytes();
+        assert_ne!(first, rng.bytes::<32>());
+        assert_eq!(rng.bytes::<0>(), []);
+
+        let vec = rng.bytes_vec(40);
+        assert_eq!(vec.len(), 40);
+        assert_ne!(vec, rng.bytes_vec(40));
+        assert!(rng.bytes_vec(0).is_empty());
+    }
+
+    #[test]
+    fn random_account_ids_are_valid_and_unique() {
+        let mut rng = test_rng(16);
+        let mut ids = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let id = rng.account_id("testnet");
+            let (name, suffix) = id.as_str().split_once('.').unwrap();
+            assert_eq!((name.len(), suffix), (12, "testnet"));
+            assert_eq!(id.as_str().parse::<AccountId>().unwrap(), id);
+            ids.insert(id.to_string());
+        }
+        assert_eq!(ids.len(), 100);
+
+        assert_eq!(rng.account_id("").as_str().len(), 12);
+        let long_suffix = format!("{}.near", "a".repeat(56));
+        assert_eq!(rng.account_id(&long_suffix).as_str().len(), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid suffix")]
+    fn account_id_rejects_invalid_suffix() {
+        setup([16; 32]);
+        SecureRng::new().account_id("Not Valid");
+    }
+
+    #[test]
+    fn equality_tracks_seed_and_position() {
+        let mut rng = test_rng(39);
+        let mut copy = rng.clone();
+        assert_eq!(copy, rng);
+        copy.next_u32();
+        assert_ne!(copy, rng);
+        rng.next_u32();
+        assert_eq!(copy, rng);
+        assert_eq!(rng.fork(b"a"), rng.fork(b"a"));
+        assert_ne!(rng.fork(b"a"), rng.fork(b"b"));
+        assert_ne!(SecureRng::from_seed([1; 32]), SecureRng::from_seed([2; 32]));
+
+        let debug = format!("{:?}", SecureRng::from_seed([7; 32]));
+        assert_eq!(debug, format!("SecureRng {{ seed: {:?}, word_pos: 0 }}", [7u8; 32]));
+    }
+
+    #[test]
+    fn serialized_state_resumes_stream() {
+        let mut rng = test_rng(10);
+        for _ in 0..7 {
+            rng.next_u32();
+        }
+        let bytes = borsh::to_vec(&rng).unwrap();
+        assert_eq!(bytes.len(), 48);
+        let mut restored: SecureRng = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(restored, rng);
+        for _ in 0..16 {
+            assert_eq!(restored.next_u32(), rng.next_u32());
+        }
+    }
+
+    #[test]
+    fn deterministic_game_id_reproduces_layout() {
+        let layout = |rng: &mut SecureRng| {
+            let mut board: Vec<u8> = (0..16).collect();
+            rng.shuffle(&mut board);
+            board
+        };
+
+        setup([1; 32]);
+        let first = layout(&mut SecureRng::from_game_id_deterministic("puzzle-42"));
+        let other = layout(&mut SecureRng::from_game_id_deterministic("puzzle-43"));
+        setup([2; 32]);
+        let second = layout(&mut SecureRng::from_game_id_deterministic("puzzle-42"));
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn game_id_mixes_block_seed() {
+        setup([1; 32]);
+        let a = SecureRng::from_game_id("puzzle-42").next_u64();
+        let deterministic = SecureRng::from_game_id_deterministic("puzzle-42").next_u64();
+        setup([2; 32]);
+        let b = SecureRng::from_game_id("puzzle-42").next_u64();
+        assert_ne!(a, b);
+        assert_ne!(a, deterministic);
+    }
+
+    #[test]
+    fn token_traits_diverge_and_reproduce() {
+        setup([7; 32]);
+        let first = SecureRng::for_token("token-1").next_u64();
+        let second = SecureRng::for_token("token-2").next_u64();
+        assert_ne!(first, second);
+        assert_eq!(first, SecureRng::for_token("token-1").next_u64());
+
+        setup([8; 32]);
+        assert_ne!(first, SecureRng::for_token("token-1").next_u64());
+        assert_eq!(first, SecureRng::for_token_with_block_seed("token-1", [7; 32]).next_u64());
+    }
+
+    #[test]
+    fn split_streams_are_addressable() {
+        setup([5; 32]);
+        let parent = SecureRng::new();
+        let draw = |domain: &str, index: u64| parent.clone().split(domain, index).next_u64();
+        assert_eq!(draw("combat", 1), draw("combat", 1));
+        assert_ne!(draw("combat", 1), draw("combat", 2));
+        assert_ne!(draw("combat", 1), draw("loot", 1));
+
+        let mut parent = parent;
+        let first = parent.split("combat", 1).next_u64();
+        assert_ne!(first, parent.split("combat", 1).next_u64());
+    }
+
+    #[test]
+    fn remaining_words_decrease_with_draws() {
+        let mut rng = test_rng(6);
+        let initial = rng.stream_remaining_words();
+        assert_eq!(initial, 1 << 68);
+        rng.next_u32();
+        let after_one = rng.stream_remaining_words();
+        assert!(after_one < initial);
+        rng.next_u64();
+        assert!(rng.stream_remaining_words() < after_one);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = test_rng(5);
+        for _ in 0..1000 {
+            assert!((10..20).contains(&rng.u8(10..20)));
+            assert!((10..20).contains(&rng.u16(10..20)));
+            assert!((10..20).contains(&rng.u32(10..20)));
+            assert!((10..20).contains(&rng.u64(10..20)));
+            assert!((10..20).contains(&rng.usize(10..20)));
+            assert!((-10..10).contains(&rng.i32(-10..10)));
+            assert!((-10..10).contains(&rng.i64(-10..10)));
+            assert!((0.0..1.0).contains(&rng.f64()));
+            assert!((1..=6).contains(&rng.roll_die(6)));
+            assert!(rng.percentage() <= 100);
+        }
+    }
+
+    #[test]
+    fn full_ranges_cover_the_whole_type() {
+        let mut rng = test_rng(29);
+        let (mut u8s, mut u16s, mut u32s, mut u64s) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..256 {
+            u8s.push(rng.u8(..));
+            u16s.push(rng.u16(..));
+            u32s.push(rng.u32(..));
+            u64s.push(rng.u64(..));
+        }
+        // Every half of each type is hit: the probability of missing one is 2^-255.
+        assert!(u8s.iter().any(|&v| v < 1 << 7) && u8s.iter().any(|&v| v >= 1 << 7));
+        assert!(u16s.iter().any(|&v| v < 1 << 15) && u16s.iter().any(|&v| v >= 1 << 15));
+        assert!(u32s.iter().any(|&v| v < 1 << 31) && u32s.iter().any(|&v| v >= 1 << 31));
+        assert!(u64s.iter().any(|&v| v < 1 << 63) && u64s.iter().any(|&v| v >= 1 << 63));
+    }
+
+    #[test]
+    fn roll_die_covers_the_widest_die() {
+        let mut seen_max = false;
+        for seed in 0..=u8::MAX {
+            let mut rng = test_rng(seed);
+            for _ in 0..64 {
+                let roll = rng.roll_die(u8::MAX);
+                assert!(roll >= 1, "{roll}");
+                seen_max |= roll == u8::MAX;
+            }
+        }
+        assert!(seen_max);
+    }
+
+    #[test]
+    fn advantage_skews_rolls() {
+        let (mut single, mut advantage, mut disadvantage) = (0u32, 0u32, 0u32);
+        for seed in 0..100 {
+            let mut rng = test_rng(seed);
+            for _ in 0..20 {
+                single += u32::from(rng.roll_die(20));
+                let best = rng.roll_advantage(20);
+                let worst = rng.roll_disadvantage(20);
+                assert!((1..=20).contains(&best) && (1..=20).contains(&worst));
+                advantage += u32::from(best);
+                disadvantage += u32::from(worst);
+            }
+        }
+        // Over 2000 rolls of a d20 the means are 10.5, about 13.8 and about 7.2.
+        assert!(advantage > single + 4_000, "{advantage} {single}");
+        assert!(disadvantage + 4_000 < single, "{disadvantage} {single}");
+    }
+
+    #[test]
+    fn keep_highest_drops_lowest_rolls() {
+        let mut rng = test_rng(37);
+        for _ in 0..1000 {
+            assert!((3..=18).contains(&rng.roll_keep_highest(4, 6, 3)));
+            assert!((3..=18).contains(&rng.roll_keep_highest(3, 6, 10)));
+            assert_eq!(rng.roll_keep_highest(5, 1, 2), 2);
+        }
+        let (mut kept, mut plain) = (0, 0);
+        for _ in 0..1000 {
+            kept += rng.roll_keep_highest(4, 6, 3);
+            plain += rng.roll_dice(3, 6);
+        }
+        assert!(kept > plain + 1_000, "{kept} {plain}");
+        assert_eq!(rng.roll_keep_highest(0, 6, 3), 0);
+        assert_eq!(rng.roll_keep_highest(4, 0, 3), 0);
+        assert_eq!(rng.roll_keep_highest(4, 6, 0), 0);
+    }
+
+    #[test]
+    fn basis_points_and_per_mille_are_uniform() {
+        let mut rng = test_rng(19);
+        let mut bps = [0u64; 10];
+        let mut mille = [0u64; 10];
+        for _ in 0..20_000 {
+            let value = rng.basis_points();
+            assert!(value <= 10_000);
+            bps[usize::from(value / 1_000).min(9)] += 1;
+            let value = rng.per_mille();
+            assert!(value <= 1_000);
+            mille[usize::from(value / 100).min(9)] += 1;
+        }
+        // 9 degrees of freedom: the statistic exceeds 27.9 with probability below 0.1%.
+        let statistic = crate::test_utils::chi_square_uniform(&bps, 2_000.0);
+        assert!(statistic < 27.9, "{bps:?}");
+        let statistic = crate::test_utils::chi_square_uniform(&mille, 2_000.0);
+        assert!(statistic < 27.9, "{mille:?}");
+    }
+
+    #[test]
+    fn json_draws_serialize_as_strings() {
+        let mut rng = test_rng(20);
+        let value = rng.u64_json(u64::MAX - 10..);
+        assert!(value.0 >= u64::MAX - 10);
+        assert_eq!(serde_json::to_string(&value).unwrap(), format!("\"{}\"", value.0));
+
+        let value = rng.u128_json(1 << 100..1 << 101);
+        assert!((1 << 100..1 << 101).contains(&value.0));
+        let json = serde_json::to_value(value).unwrap();
+        assert_eq!(json, value.0.to_string().as_str());
+    }
+
+    #[test]
+    fn float_ranges_stay_within_bounds() {
+        let mut rng = test_rng(21);
+        let (mut below_zero, mut above_zero) = (false, false);
+        for _ in 0..1_000 {
+            let value = rng.f64_range(-10.0..10.0);
+            assert!((-10.0..10.0).contains(&value), "{value}");
+            below_zero |= value < 0.0;
+            above_zero |= value > 0.0;
+            assert!((250.5..251.0).contains(&rng.f64_range(250.5..251.0)));
+            assert!((-3.5..-1.25).contains(&rng.f32_range(-3.5..-1.25)));
+            assert!((0.0..360.0).contains(&rng.f32_range(0.0..360.0)));
+        }
+        assert!(below_zero && above_zero);
+        assert!(rng.f64_range(f64::MIN..f64::MAX).is_finite());
+        assert!(rng.f32_range(f32::MIN..f32::MAX).is_finite());
+    }
+
+    #[test]
+    #[should_panic(expected = "SecureRng: empty or non-finite range 1.0..1.0 requested")]
+    fn empty_float_range() {
+        SecureRng::from_seed([21; 32]).f64_range(1.0..1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "SecureRng: empty or non-finite range 0.0..inf requested")]
+    fn infinite_float_range() {
+        SecureRng::from_seed([21; 32]).f32_range(0.0..f32::INFINITY);
+    }
+
+    #[test]
+    fn chance_matches_probability() {
+        let mut rng = test_rng(18);
+        assert!((0..1_000).all(|_| !rng.chance(0.0) && rng.chance(1.0)));
+        assert!((0..100).all(|_| !rng.chance(-3.0) && rng.chance(7.0) && !rng.chance(f64::NAN)));
+        let hits = (0..10_000).filter(|_| rng.chance(0.25)).count();
+        assert!((2_350..2_650).contains(&hits), "{hits}");
+    }
+
+    #[test]
+    fn dice_sums() {
+        let mut rng = test_rng(15);
+        let mut seen = [false; 19];
+        for _ in 0..2_000 {
+            let sum = rng.roll_dice(3, 6);
+            assert!((3..=18).contains(&sum));
+            seen[sum as usize] = true;
+        }
+        assert!(seen[3..].iter().all(|&seen| seen));
+
+        let rolls = rng.roll_dice_vec(4, 20);
+        assert_eq!(rolls.len(), 4);
+        assert!(rolls.iter().all(|roll| (1..=20).contains(roll)));
+        assert_eq!(rng.roll_dice(0, 6), 0);
+        assert_eq!(rng.roll_dice(3, 0), 0);
+        assert!(rng.roll_dice_vec(3, 0).is_empty());
+        assert!(rng.roll_dice(255, 255) <= 255 * 255);
+    }
+
+    #[test]
+    fn inclusive_and_open_ranges() {
+        let mut rng = test_rng(5);
+        let mut seen_u8 = [false; 256];
+        for _ in 0..10_000 {
+            seen_u8[usize::from(rng.u8(0..=u8::MAX))] = true;
+            assert!((1..=6).contains(&rng.u8(1..=6)));
+            assert!(rng.u16(65_530..) >= 65_530);
+            assert!(rng.u32(..3) < 3);
+            assert!(rng.usize(..=3) <= 3);
+            assert!((-3..=3).contains(&rng.i32(-3..=3)));
+            assert!(rng.i64(..0) < 0);
+        }
+        assert!(seen_u8.iter().all(|&seen| seen));
+        assert_eq!(rng.u8(u8::MAX..=u8::MAX), u8::MAX);
+        assert_eq!(rng.u64(u64::MAX..), u64::MAX);
+        assert_eq!(rng.i64(..=i64::MIN), i64::MIN);
+
+        let draws: Vec<u64> = (0..64).map(|_| rng.u64(0..=u64::MAX)).collect();
+        assert!(draws.iter().any(|&draw| draw > u64::MAX / 2));
+        assert!(draws.iter().any(|&draw| draw <= u64::MAX / 2));
+    }
+
+    #[test]
+    fn bulk_u32_draws() {
+        let mut rng = test_rng(12);
+        let mut tiles = [0; 64];
+        rng.fill_u32(3..9, &mut tiles);
+        assert!(tiles.iter().all(|tile| (3..9).contains(tile)));
+
+        let first = rng.gen_vec_u32(0..=1000, 32);
+        let second = rng.gen_vec_u32(0..=1000, 32);
+        assert_eq!(first.len(), 32);
+        assert!(first.iter().chain(&second).all(|value| *value <= 1000));
+        assert_ne!(first, second);
+        assert!(rng.gen_vec_u32(5..5, 0).is_empty());
+    }
+
+    #[test]
+    fn wide_ranges() {
+        let mut rng = test_rng(8);
+        let (low, high) = (u128::from(u64::MAX) * 3, u128::MAX / 2);
+        let mut above_u64 = false;
+        for _ in 0..1000 {
+            let amount = rng.u128(0..u128::MAX / 2);
+            assert!(amount < u128::MAX / 2);
+            above_u64 |= amount > u128::from(u64::MAX);
+            assert!((low..high).contains(&rng.u128(low..high)));
+            let signed = rng.i128(i128::MIN / 2..i128::MAX / 2);
+            assert!((i128::MIN / 2..i128::MAX / 2).contains(&signed));
+        }
+        assert!(above_u64);
+        assert_eq!(rng.u128(u128::MAX..), u128::MAX);
+        assert_eq!(rng.i128(..=i128::MIN), i128::MIN);
+    }
+
+    #[test]
+    #[should_panic(expected = "SecureRng: empty range 255 (excluded).. requested")]
+    fn excluded_bound_past_max_is_empty() {
+        setup([5; 32]);
+        SecureRng::new().u8((Bound::Excluded(u8::MAX), Bound::Unbounded));
+    }
+
+    macro_rules! empty_range_tests {
+        ($($name:ident: $method:ident($range:expr) => $message:literal;)*) => {$(
+            #[test]
+            #[should_panic(expected = $message)]
+            #[allow(clippy::reversed_empty_ranges)]
+            fn $name() {
+                SecureRng::from_seed([5; 32]).$method($range);
+            }
+        )*};
+    }
+
+    empty_range_tests! {
+        empty_u8_range: u8(5..5) => "SecureRng: empty range 5..5 requested";
+        empty_u16_range: u16(7..=3) => "SecureRng: empty range 7..=3 requested";
+        empty_u32_range: u32(5..5) => "SecureRng: empty range 5..5 requested";
+        empty_u64_range: u64(..0) => "SecureRng: empty range ..0 requested";
+        empty_u128_range: u128(9..1) => "SecureRng: empty range 9..1 requested";
+        empty_usize_range: usize(0..0) => "SecureRng: empty range 0..0 requested";
+        empty_i32_range: i32(-3..-3) => "SecureRng: empty range -3..-3 requested";
+        empty_i64_range: i64(10..=-10) => "SecureRng: empty range 10..=-10 requested";
+        empty_i128_range: i128(1..=0) => "SecureRng: empty range 1..=0 requested";
+        zero_sided_die: roll_die(0) => "SecureRng: empty range 1..=0 requested";
+        zero_sided_advantage: roll_advantage(0) => "SecureRng: empty range 1..=0 requested";
+        zero_sided_disadvantage: roll_disadvantage(0) => "SecureRng: empty range 1..=0 requested";
+    }
+
+    #[test]
+    fn choice_and_shuffle() {
+        let mut rng = test_rng(6);
+        assert_eq!(rng.choice::<u8>(&[]), None);
+        assert_eq!(rng.choice(&[7]), Some(&7));
+
+        let mut items: Vec<u32> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn choice_index_and_mut() {
+        let mut rng = test_rng(6);
+        assert_eq!(rng.choice_index::<u8>(&[]), None);
+        assert_eq!(rng.choice_mut::<u8>(&mut []), None);
+
+        let mut balances = [0u32; 5];
+        for _ in 0..500 {
+            let index = rng.choice_index(&balances).unwrap();
+            assert!(index < balances.len());
+            balances[index] += 1;
+        }
+        assert!(balances.iter().all(|&count| count > 0));
+
+        for _ in 0..100 {
+            *rng.choice_mut(&mut balances).unwrap() += 1;
+        }
+        assert_eq!(balances.iter().sum::<u32>(), 600);
+    }
+
+    #[test]
+    fn choice_where_only_returns_matches() {
+        let mut rng = test_rng(22);
+        let participants: Vec<(u32, bool)> = (0..30).map(|id| (id, id % 3 == 0)).collect();
+        let mut counts = [0u64; 10];
+        for _ in 0..5_000 {
+            let &(id, active) = rng.choice_where(&participants, |(_, active)| *active).unwrap();
+            assert!(active);
+            counts[id as usize / 3] += 1;
+        }
+        // 9 degrees of freedom: the statistic exceeds 27.9 with probability below 0.1%.
+        let statistic = crate::test_utils::chi_square_uniform(&counts, 500.0);
+        assert!(statistic < 27.9, "{counts:?}");
+
+        assert_eq!(rng.choice_where(&participants, |_| false), None);
+        assert_eq!(rng.choice_where::<u8, _>(&[], |_| true), None);
+        assert_eq!(rng.choice_where(&[1, 2, 3], |&n| n == 2), Some(&2));
+    }
+
+    #[test]
+    fn weighted_choice_follows_weights() {
+        let mut rng = test_rng(9);
+        let items = ["common", "never", "rare"];
+        let mut counts = [0u32; 3];
+        for _ in 0..10_000 {
+            let chosen = rng.choose_weighted(&items, &[99, 0, 1]).unwrap();
+            counts[items.iter().position(|item| item == chosen).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(counts[0] > 9_700 && counts[2] > 0, "{counts:?}");
+        assert_eq!(rng.choose_weighted(&items, &[1, 1]), None);
+        assert_eq!(rng.choose_weighted(&items, &[0, 0, 0]), None);
+        assert_ne!(rng.choose_weighted(&items, &[0, u64::MAX, u64::MAX]), Some(&"common"));
+    }
+
+    #[test]
+    fn partial_shuffle_front_is_uniform_sample() {
+        let mut front = [[0u64; 10]; 3];
+        for seed in 0..2000u32 {
+            let mut seed_bytes = [0; 32];
+            seed_bytes[..4].copy_from_slice(&seed.to_le_bytes());
+            let mut items: Vec<u32> = (0..10).collect();
+            SecureRng::from_seed(seed_bytes).shuffle_partial(&mut items, 3);
+            let mut sorted = items.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+            for (position, &item) in items[..3].iter().enumerate() {
+                front[position][item as usize] += 1;
+            }
+        }
+        // 9 degrees of freedom: the statistic exceeds 30 with probability below 0.1%.
+        for counts in front {
+            let statistic = crate::test_utils::chi_square_uniform(&counts, 200.0);
+            assert!(statistic < 30.0, "{statistic}");
+        }
+
+        let mut rng = SecureRng::from_seed([0; 32]);
+        let mut items = [1, 2, 3];
+        rng.shuffle_partial(&mut items, 10);
+        items.sort_unstable();
+        assert_eq!(items, [1, 2, 3]);
+        rng.shuffle_partial::<u8>(&mut [], 2);
+    }
+
+    #[test]
+    fn sample_multiple_distinct() {
+        let mut rng = test_rng(7);
+        let items: Vec<u32> = (0..20).collect();
+        let mut sample = rng.sample_multiple(&items, 5);
+        assert_eq!(sample.len(), 5);
+        sample.sort_unstable();
+        sample.dedup();
+        assert_eq!(sample.len(), 5);
+        assert_eq!(rng.sample_multiple(&items, 30).len(), 20);
+
+        let position = rng.peek_u32();
+        assert_eq!(rng.sample_multiple(&items, usize::MAX), items.iter().collect::<Vec<_>>());
+        assert_eq!(rng.peek_u32(), position);
+        assert!(rng.sample_multiple::<u32>(&[], usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn sample_ordered_is_a_subsequence() {
+        let mut rng = test_rng(38);
+        let items: Vec<u32> = (0..50).collect();
+        let mut first = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let sample = rng.sample_ordered(&items, 7);
+            assert_eq!(sample.len(), 7);
+            assert!(sample.windows(2).all(|pair| pair[0] < pair[1]), "{sample:?}");
+            first.insert(*sample[0]);
+        }
+        assert!(first.len() > 10, "{first:?}");
+        assert_eq!(rng.sample_ordered(&items, 60), items.iter().collect::<Vec<_>>());
+        assert!(rng.sample_ordered(&items, 0).is_empty());
+        assert!(rng.sample_ordered::<u32>(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn sample_multiple_is_uniform() {
+        let items: Vec<u32> = (0..1000).collect();
+        let mut hits = vec![0u64; items.len()];
+        let mut first = vec![0u64; 10];
+        for seed in 0..3000u32 {
+            let mut seed_bytes = [0; 32];
+            seed_bytes[..4].copy_from_slice(&seed.to_le_bytes());
+            let sample = SecureRng::from_seed(seed_bytes).sample_multiple(&items, 3);
+            assert_eq!(sample.len(), 3);
+            assert!(sample[0] != sample[1] && sample[1] != sample[2] && sample[0] != sample[2]);
+            for &&item in &sample {
+                hits[item as usize] += 1;
+            }
+            first[*sample[0] as usize / 100] += 1;
+        }
+        // 999 degrees of freedom: the statistic exceeds 1150 with probability below 0.1%.
+        let statistic = crate::test_utils::chi_square_uniform(&hits, 9.0);
+        assert!(statistic < 1150.0, "{statistic}");
+        let statistic = crate::test_utils::chi_square_uniform(&first, 300.0);
+        assert!(statistic < 30.0, "{statistic}");
+    }
+}
@@ -0,0 +1,840 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+use std::time::Duration;
+
+use super::{Rng, RngCore, SecureRng};
+use crate::env;
+
+impl SecureRng {
+    /// Draws `draws` indices with replacement, each with probability proportional to its
+    /// weight, and returns how many times each index was drawn.
+    ///
+    /// The counts sum to `draws`, unless no weight is positive, in which case they are all
+    /// zero.
+    pub fn sample_histogram(&mut self, weights: &[u64], draws: usize) -> Vec<u64> {
+        let mut counts = vec![0; weights.len()];
+        let cumulative = cumulative_weights(weights);
+        let total = cumulative.last().copied().unwrap_or(0);
+        if total == 0 {
+            return counts;
+        }
+        for _ in 0..draws {
+            counts[index_for(&cumulative, self.u128(0..total))] += 1;
+        }
+        counts
+    }
+
+    /// Returns a uniformly distributed value in `range` other than `exclude`, without
+    /// rejection sampling: it draws from a range one element shorter and shifts values at or
+    /// above `exclude` up by one.
+    ///
+    /// Panics if `range` has no element other than `exclude`.
+    pub fn usize_excluding(&mut self, range: Range<usize>, exclude: usize) -> usize {
+        if !range.contains(&exclude) {
+            return self.usize(range);
+        }
+        if range.len() == 1 {
+            env::panic_str("usize_excluding: the range contains only the excluded value");
+        }
+        let value = self.usize(range.start..range.end - 1);
+        if value >= exclude { value + 1 } else { value }
+    }
+
+    /// Removes a uniformly chosen element from `vec` and returns it, or `None` if `vec` is
+    /// empty. The last element takes its place, so removal is `O(1)` but the order of `vec`
+    /// changes.
+    pub fn take_random<T>(&mut self, vec: &mut Vec<T>) -> Option<T> {
+        if vec.is_empty() {
+            return None;
+        }
+        let index = self.usize(0..vec.len());
+        Some(vec.swap_remove(index))
+    }
+
+    /// Draws an item from `inventory` with probability proportional to its remaining count,
+    /// decrements that count and returns the item, or `None` if no count is positive.
+    ///
+    /// Items whose count reaches zero are removed. Items are considered in sorted order rather
+    /// than in the iteration order of the map, which is not deterministic.
+    pub fn draw_from_inventory<T: Clone + Ord + Hash>(
+        &mut self,
+        inventory: &mut HashMap<T, u64>,
+    ) -> Option<T> {
+        let mut items: Vec<(&T, u64)> =
+            inventory.iter().map(|(item, &count)| (item, count)).collect();
+        items.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let counts: Vec<u64> = items.iter().map(|&(_, count)| count).collect();
+        let cumulative = cumulative_weights(&counts);
+        let total = cumulative.last().copied().filter(|&total| total > 0)?;
+        let item = items[index_for(&cumulative, self.u128(0..total))].0.clone();
+
+        let count = inventory.get_mut(&item).unwrap_or_else(|| unreachable!());
+        *count -= 1;
+        if *count == 0 {
+            inventory.remove(&item);
+        }
+        Some(item)
+    }
+
+    /// Returns a uniformly chosen element of `items` among those whose flag in `eligible` is
+    /// set, or `None` if no element is eligible.
+    ///
+    /// Panics if `items` and `eligible` differ in length.
+    pub fn choose_eligible<'a, T>(&mut self, items: &'a [T], eligible: &[bool]) -> Option<&'a T> {
+        if items.len() != eligible.len() {
+            env::panic_str("choose_eligible: items and eligible flags differ in length");
+        }
+        let count = eligible.iter().filter(|&&flag| flag).count();
+        if count == 0 {
+            return None;
+        }
+        let nth = self.usize(0..count);
+        items.iter().zip(eligible).filter(|&(_, &flag)| flag).nth(nth).map(|(item, _)| item)
+    }
+
+    /// Returns a uniformly chosen element of `items` that is not among the last `window`
+    /// entries of `recent`, a selection history ordered from oldest to newest. Falls back to
+    /// all of `items` if every one of them was selected recently, and returns `None` only if
+    /// `items` is empty.
+    pub fn choose_avoiding_recent<'a, T: PartialEq>(
+        &mut self,
+        items: &'a [T],
+        recent: &[T],
+        window: usize,
+    ) -> Option<&'a T> {
+        let recent = &recent[recent.len().saturating_sub(window)..];
+        let fresh: Vec<&T> = items.iter().filter(|item| !recent.contains(item)).collect();
+        if fresh.is_empty() { self.choice(items) } else { self.choice(&fresh).copied() }
+    }
+
+    /// Returns an element of `items` chosen with a weight that halves for every `half_life`
+    /// elapsed between its timestamp and `now`, so that recent items are favored. Returns
+    /// `None` if `items` is empty.
+    ///
+    /// Timestamps after `now` count as current and a `half_life` of zero is treated as one.
+    pub fn choose_recency_weighted<'a, T>(
+        &mut self,
+        items: &'a [(T, u64)],
+        now: u64,
+        half_life: u64,
+    ) -> Option<&'a T> {
+        let newest = items.iter().map(|(_, timestamp)| now.saturating_sub(*timestamp)).min()?;
+        let half_life = half_life.max(1) as f64;
+        // Ages are taken relative to the newest item so its weight is exactly one and the
+        // total cannot underflow to zero.
+        let weights: Vec<f64> = items
+            .iter()
+            .map(|(_, timestamp)| {
+                let age = now.saturating_sub(*timestamp) - newest;
+                (-(age as f64) / half_life).exp2()
+            })
+            .collect();
+        let mut target = self.f64() * weights.iter().sum::<f64>();
+        for ((item, _), weight) in items.iter().zip(&weights) {
+            if target < *weight {
+                return Some(item);
+            }
+            target -= weight;
+        }
+        // Rounding can leave a tiny remainder past the last weight.
+        items.last().map(|(item, _)| item)
+    }
+
+    /// Returns a uniformly chosen member that is not cooling down, or `None` if every member
+    /// is.
+    ///
+    /// A member is cooling down while fewer than `cooldown` units have passed since any of its
+    /// selections recorded in `last_selected`. Members without a recorded selection are always
+    /// eligible.
+    pub fn choose_with_cooldown<'a, T: PartialEq>(
+        &mut self,
+        members: &'a [T],
+        last_selected: &[(T, u64)],
+        now: u64,
+        cooldown: u64,
+    ) -> Option<&'a T> {
+        let eligible: Vec<&T> = members
+            .iter()
+            .filter(|member| {
+                last_selected.iter().all(|(selected, at)| {
+                    selected != *member || now.saturating_sub(*at) >= cooldown
+                })
+            })
+            .collect();
+        self.choice(&eligible).copied()
+    }
+
+    /// Returns an element of `items`, given as `(item, joined_at, base_weight)`, chosen with a
+    /// weight of `base_weight` times the time it has participated up to `now`.
+    ///
+    /// Entries that join at or after `now` cannot win. Returns `None` if no entry has a
+    /// positive effective weight. Effective weights whose sum overflows a `u128` are scaled
+    /// down as in [`choose_weighted_u128`](Self::choose_weighted_u128).
+    pub fn choose_time_weighted<'a, T>(
+        &mut self,
+        items: &'a [(T, u64, u64)],
+        now: u64,
+    ) -> Option<&'a T> {
+        // A product of two u64 values always fits in a u128; only the sum can overflow.
+        let weights: Vec<u128> = items
+            .iter()
+            .map(|(_, joined_at, base_weight)| {
+                u128::from(now.saturating_sub(*joined_at)) * u128::from(*base_weight)
+            })
+            .collect();
+        self.choose_weighted_u128(items, &weights).map(|(item, ..)| item)
+    }
+
+    /// Chooses an element of `items` with probability proportional to `weights`, deriving the
+    /// draw from `salt` and the stream, and returns it together with a proof hash.
+    ///
+    /// The proof is `sha256(stream bytes || salt)` and alone determines the choice, so players
+    /// can check a published result with
+    /// [`weighted_index_from_proof`](Self::weighted_index_from_proof). The choice is `None` if
+    /// the slices differ in length or no weight is positive.
+    pub fn choose_weighted_salted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weights: &[u64],
+        salt: &[u8],
+    ) -> (Option<&'a T>, [u8; 32]) {
+        let mut key = [0u8; 32];
+        self.fill_bytes(&mut key);
+        let proof = env::sha256_array([&key, salt].concat());
+        if items.len() != weights.len() {
+            return (None, proof);
+        }
+        (Self::weighted_index_from_proof(weights, &proof).map(|index| &items[index]), proof)
+    }
+
+    /// Returns the index that [`choose_weighted_salted`](Self::choose_weighted_salted) selects
+    /// for `weights` given its `proof`, or `None` if no weight is positive.
+    pub fn weighted_index_from_proof(weights: &[u64], proof: &[u8; 32]) -> Option<usize> {
+        let cumulative = cumulative_weights(weights);
+        let total = cumulative.last().copied().filter(|&total| total > 0)?;
+        let mut target = [0u8; 16];
+        target.copy_from_slice(&proof[..16]);
+        Some(index_for(&cumulative, u128::from_le_bytes(target) % total))
+    }
+
+    /// Chooses an element of `items` with probability proportional to `weights` and returns it
+    /// with its luck factor, the reciprocal of its probability of being chosen. Rarer outcomes
+    /// have a higher luck factor, and a certain outcome has a luck factor of one.
+    ///
+    /// Returns `None` if the slices differ in length or no weight is positive.
+    pub fn choose_weighted_luck<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weights: &[u64],
+    ) -> Option<(&'a T, f64)> {
+        if items.len() != weights.len() {
+            return None;
+        }
+        let cumulative = cumulative_weights(weights);
+        let total = cumulative.last().copied().filter(|&total| total > 0)?;
+        let index = index_for(&cumulative, self.u128(0..total));
+        Some((&items[index], total as f64 / weights[index] as f64))
+    }
+
+    /// Returns a uniformly distributed time in `window` that lies outside every range in
+    /// `blackouts`, or `None` if the blackouts cover the whole window.
+    ///
+    /// Blackouts may overlap each other and extend past the window.
+    pub fn random_time_avoiding(
+        &mut self,
+        window: Range<u64>,
+        blackouts: &[Range<u64>],
+    ) -> Option<u64> {
+        let mut blocked: Vec<Range<u64>> = blackouts
+            .iter()
+            .map(|blackout| blackout.start.max(window.start)..blackout.end.min(window.end))
+            .filter(|blackout| !blackout.is_empty())
+            .collect();
+        blocked.sort_unstable_by_key(|blackout| blackout.start);
+
+        // Gaps between the merged blackouts, in order.
+        let mut available = Vec::new();
+        let mut cursor = window.start;
+        for blackout in blocked {
+            if blackout.start > cursor {
+                available.push(cursor..blackout.start);
+            }
+            cursor = cursor.max(blackout.end);
+        }
+        if window.end > cursor {
+            available.push(cursor..window.end);
+        }
+
+        let total: u64 = available.iter().map(|gap| gap.end - gap.start).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut offset = self.u64(0..total);
+        for gap in available {
+            let len = gap.end - gap.start;
+            if offset < len {
+                return Some(gap.start + offset);
+            }
+            offset -= len;
+        }
+        unreachable!()
+    }
+
+    /// Returns a retry delay in nanoseconds for exponential backoff with full jitter: a
+    /// uniformly distributed value below `base * 2^attempt`, where the ceiling saturates at
+    /// `max`.
+    ///
+    /// Spreading retries over the whole interval keeps failed calls from retrying in lockstep.
+    /// The result is always below `max`, or zero if the ceiling is zero.
+    pub fn jitter(&mut self, base: u64, attempt: u32, max: u64) -> u64 {
+        let ceiling = 1u64
+            .checked_shl(attempt)
+            .and_then(|factor| base.checked_mul(factor))
+            .map_or(max, |delay| delay.min(max));
+        if ceiling == 0 { 0 } else { self.u64(0..ceiling) }
+    }
+
+    /// Returns a uniformly distributed duration in `[min, max)`, with nanosecond precision,
+    /// for randomized cooldowns or vesting cliffs.
+    ///
+    /// Returns `min` if `min >= max`.
+    pub fn duration(&mut self, min: Duration, max: Duration) -> Duration {
+        if min >= max {
+            return min;
+        }
+        let nanos = self.u128(min.as_nanos()..max.as_nanos());
+        // Below `max.as_nanos()`, so the whole seconds fit in a u64.
+        Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+    }
+
+    /// Returns `sample_size` distinct voter indices in `0..voter_count`, or all of them if
+    /// `sample_size` is not smaller than `voter_count`.
+    ///
+    /// Memory and draws scale with the sample rather than the electorate, so sampling a few
+    /// voters out of millions is cheap.
+    pub fn poll_sample(&mut self, voter_count: usize, sample_size: usize) -> Vec<usize> {
+        rand::seq::index::sample(self, voter_count, sample_size.min(voter_count)).into_vec()
+    }
+
+    /// Draws `per_stratum` elements without replacement from every stratum, or all elements
+    /// of smaller strata, and returns them grouped by stratum in order.
+    pub fn stratified_sample<T: Clone>(&mut self, strata: &[Vec<T>], per_stratum: usize) -> Vec<T> {
+        let mut sample = Vec::new();
+        for stratum in strata {
+            sample.extend(self.sample_multiple(stratum, per_stratum).into_iter().cloned());
+        }
+        sample
+    }
+
+    /// Draws the requested number of elements from each pool in order, never returning an
+    /// element equal to one already drawn from this or an earlier pool.
+    ///
+    /// A pool yields fewer elements than requested once it runs out of unused elements.
+    pub fn multi_pool_sample<T: Clone + PartialEq>(
+        &mut self,
+        pools: &[(Vec<T>, usize)],
+    ) -> Vec<Vec<T>> {
+        let mut drawn: Vec<T> = Vec::new();
+        let mut samples = Vec::with_capacity(pools.len());
+        for (pool, count) in pools {
+            let mut order: Vec<usize> = (0..pool.len()).collect();
+            self.shuffle(&mut order);
+            let mut sample = Vec::with_capacity((*count).min(pool.len()));
+            for index in order {
+                if sample.len() == *count {
+                    break;
+                }
+                let item = &pool[index];
+                if !drawn.contains(item) {
+                    drawn.push(item.clone());
+                    sample.push(item.clone());
+                }
+            }
+            samples.push(sample);
+        }
+        samples
+    }
+
+    /// Picks the winner among equally ranked candidates, such as equal bids, uniformly at
+    /// random. Returns `None` if `tied` is empty.
+    pub fn break_ties<'a, T>(&mut self, tied: &'a [T]) -> Option<&'a T> {
+        self.choice(tied)
+    }
+
+    /// Picks the winner among equally ranked candidates paired with their bid timestamps,
+    /// favoring earlier bids.
+    ///
+    /// Every candidate is weighted by how much earlier than the latest bid it was placed, plus
+    /// one, so the latest bidder keeps a chance to win. Returns `None` if `tied` is empty.
+    pub fn break_ties_weighted<'a, T>(&mut self, tied: &'a [(T, u64)]) -> Option<&'a T> {
+        let latest = tied.iter().map(|(_, bid_at)| *bid_at).max()?;
+        let cumulative: Vec<u128> = tied
+            .iter()
+            .scan(0u128, |total, (_, bid_at)| {
+                *total += u128::from(latest - bid_at) + 1;
+                Some(*total)
+            })
+            .collect();
+        let total = cumulative[cumulative.len() - 1];
+        Some(&tied[index_for(&cumulative, self.u128(0..total))].0)
+    }
+
+    /// Returns an element of `items` chosen with probability proportional to its weight in
+    /// `weights`, for `u128` weights such as staked balances whose sum may not fit in a
+    /// `u128`.
+    ///
+    /// If the sum overflows, every weight is halved, rounding down, until it fits. This only
+    /// drops the lowest bits of each weight, but weights small enough to be halved to zero can
+    /// no longer be chosen. Returns `None` if the slices differ in length or no weight is
+    /// positive.
+    pub fn choose_weighted_u128<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weights: &[u128],
+    ) -> Option<&'a T> {
+        if items.len() != weights.len() {
+            return None;
+        }
+        // Shifting every weight right by 127 leaves at most one per item, so this always ends.
+        let cumulative = (0..u128::BITS)
+            .find_map(|shift| checked_cumulative_weights(weights, shift))
+            .unwrap_or_else(|| unreachable!());
+        let total = cumulative.last().copied().filter(|&total| total > 0)?;
+        Some(&items[index_for(&cumulative, self.u128(0..total))])
+    }
+}
+
+/// Running totals of `weights`, accumulated as `u128` so that they cannot overflow.
+pub(super) fn cumulative_weights(weights: &[u64]) -> Vec<u128> {
+    weights
+        .iter()
+        .scan(0u128, |total, &weight| {
+            *total += u128::from(weight);
+            Some(*total)
+        })
+        .collect()
+}
+
+/// Running totals of `weights` shifted right by `shift`, or `None` if they overflow.
+fn checked_cumulative_weights(weights: &[u128], shift: u32) -> Option<Vec<u128>> {
+    let mut total = 0u128;
+    weights
+        .iter()
+        .map(|&weight| {
+            total = total.checked_add(weight >> shift)?;
+            Some(total)
+        })
+        .collect()
+}
+
+/// Returns the index of the first running total exceeding `target`, which is never the index
+/// of a zero weight.
+pub(super) fn index_for(cumulative: &[u128], target: u128) -> usize {
+    cumulative.partition_point(|&total| total <= target)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::random::{SecureRng, test_rng};
+
+    #[test]
+    fn histogram_tracks_weights() {
+        let counts = test_rng(1).sample_histogram(&[1, 0, 3], 40_000);
+        assert_eq!(counts.iter().sum::<u64>(), 40_000);
+        assert_eq!(counts[1], 0);
+        let ratio = counts[2] as f64 / counts[0] as f64;
+        assert!((2.7..3.3).contains(&ratio), "ratio {ratio}");
+    }
+
+    #[test]
+    fn excluded_value_never_drawn() {
+        let mut rng = test_rng(14);
+        let mut seen = [0u32; 5];
+        for _ in 0..5_000 {
+            seen[rng.usize_excluding(0..5, 2)] += 1;
+        }
+        assert_eq!(seen[2], 0);
+        assert!(seen.iter().enumerate().all(|(i, &count)| i == 2 || count > 1_100), "{seen:?}");
+        assert!((0..100).all(|_| rng.usize_excluding(3..5, 4) == 3));
+        assert!((0..100).all(|_| rng.usize_excluding(3..5, 3) == 4));
+        assert!((0..100).all(|_| (3..5).contains(&rng.usize_excluding(3..5, 9))));
+    }
+
+    #[test]
+    #[should_panic(expected = "only the excluded value")]
+    fn excluding_the_only_value() {
+        test_rng(15).usize_excluding(7..8, 7);
+    }
+
+    #[test]
+    fn take_random_drains_pool() {
+        let mut rng = test_rng(13);
+        let original: Vec<u32> = (0..30).collect();
+        let mut pool = original.clone();
+        let mut drawn = Vec::new();
+        while let Some(winner) = rng.take_random(&mut pool) {
+            drawn.push(winner);
+        }
+        assert!(pool.is_empty());
+        assert_ne!(drawn, original);
+        drawn.sort_unstable();
+        assert_eq!(drawn, original);
+        assert_eq!(rng.take_random(&mut vec!['x']), Some('x'));
+    }
+
+    #[test]
+    fn ineligible_items_are_never_chosen() {
+        let mut rng = test_rng(3);
+        let items = ["alice", "bob", "carol", "dave"];
+        let eligible = [false, true, false, true];
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            let chosen = rng.choose_eligible(&items, &eligible).unwrap();
+            seen[items.iter().position(|item| item == chosen).unwrap()] = true;
+        }
+        assert_eq!(seen, eligible);
+        assert_eq!(rng.choose_eligible(&items, &[false; 4]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "differ in length")]
+    fn eligible_length_mismatch() {
+        test_rng(4).choose_eligible(&[1, 2, 3], &[true]);
+    }
+
+    #[test]
+    fn recent_items_are_avoided_until_window_rolls_off() {
+        let mut rng = test_rng(12);
+        let items = ['a', 'b', 'c', 'd'];
+        let mut history = vec!['a', 'b'];
+        for _ in 0..40 {
+            let chosen = *rng.choose_avoiding_recent(&items, &history, 3).unwrap();
+            assert!(!history[history.len().saturating_sub(3)..].contains(&chosen));
+            history.push(chosen);
+        }
+        // With a window of three out of four items, selections cycle through all of them.
+        assert!(history.windows(4).all(|w| (1..4).all(|i| !w[..i].contains(&w[i]))));
+        assert!(rng.choose_avoiding_recent(&items, &['a', 'b', 'c', 'd'], 4).is_some());
+        assert_eq!(rng.choose_avoiding_recent(&items[..1], &['a'], 1), Some(&'a'));
+        assert_eq!(rng.choose_avoiding_recent(&items[..2], &['a', 'b'], 1), Some(&'a'));
+        assert_eq!(rng.choose_avoiding_recent::<char>(&[], &[], 1), None);
+    }
+
+    #[test]
+    fn recency_weight_halves_per_half_life() {
+        let mut rng = test_rng(5);
+        let now = 1_000_000;
+        let items = [("new", now), ("one", now - 100), ("two", now - 200), ("future", now + 5)];
+        let mut counts = [0u32; 4];
+        for _ in 0..40_000 {
+            let chosen = rng.choose_recency_weighted(&items, now, 100).unwrap();
+            counts[items.iter().position(|(item, _)| item == chosen).unwrap()] += 1;
+        }
+        let ratio = |a: usize, b: usize| counts[a] as f64 / counts[b] as f64;
+        assert!((1.8..2.2).contains(&ratio(0, 1)), "{counts:?}");
+        assert!((3.5..4.5).contains(&ratio(0, 2)), "{counts:?}");
+        assert!((0.9..1.1).contains(&ratio(0, 3)), "{counts:?}");
+        assert_eq!(rng.choose_recency_weighted::<u8>(&[], now, 100), None);
+    }
+
+    #[test]
+    fn every_stratum_contributes() {
+        let mut rng = test_rng(16);
+        let strata =
+            vec![(0..10).collect::<Vec<u32>>(), (10..12).collect(), vec![], (20..30).collect()];
+        for _ in 0..50 {
+            let sample = rng.stratified_sample(&strata, 3);
+            assert_eq!(sample.len(), 3 + 2 + 3);
+            for (range, count) in [(0..10, 3), (10..12, 2), (20..30, 3)] {
+                let mut from_stratum: Vec<u32> =
+                    sample.iter().copied().filter(|item| range.contains(item)).collect();
+                from_stratum.sort_unstable();
+                from_stratum.dedup();
+                assert_eq!(from_stratum.len(), count);
+            }
+        }
+        assert!(rng.stratified_sample(&strata, 0).is_empty());
+    }
+
+    #[test]
+    fn multi_pool_sample_has_no_cross_pool_duplicates() {
+        let mut rng = test_rng(6);
+        let pools = [
+            ((0..10).collect::<Vec<u32>>(), 4),
+            ((5..15).collect(), 4),
+            ((0..15).collect(), 4),
+            ((0..3).collect(), 3),
+        ];
+        for _ in 0..50 {
+            let samples = rng.multi_pool_sample(&pools);
+            for ((pool, _), sample) in pools.iter().take(3).zip(&samples) {
+                assert_eq!(sample.len(), 4);
+                assert!(sample.iter().all(|item| pool.contains(item)));
+            }
+            let mut all: Vec<u32> = samples.concat();
+            let total = all.len();
+            all.sort_unstable();
+            all.dedup();
+            assert_eq!(all.len(), total);
+        }
+    }
+
+    #[test]
+    fn members_in_cooldown_are_skipped() {
+        let mut rng = test_rng(9);
+        let members = ["alice", "bob", "carol", "dave"];
+        let now = 1_000;
+        let last_selected = [("alice", 995), ("bob", 990), ("carol", 900), ("alice", 800)];
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            let chosen = rng.choose_with_cooldown(&members, &last_selected, now, 10).unwrap();
+            seen[members.iter().position(|member| member == chosen).unwrap()] = true;
+        }
+        assert_eq!(seen, [false, true, true, true]);
+        assert_eq!(rng.choose_with_cooldown(&members[..2], &last_selected, now, 11), None);
+        assert_eq!(rng.choose_with_cooldown::<&str>(&[], &last_selected, now, 0), None);
+    }
+
+    #[test]
+    fn longer_participation_wins_more_often() {
+        let mut rng = test_rng(10);
+        let now = 10_000;
+        let items = [("veteran", 7_000, 1), ("regular", 9_000, 1), ("whale", 9_000, 3)];
+        let mut counts = [0u32; 3];
+        for _ in 0..30_000 {
+            let chosen = rng.choose_time_weighted(&items, now).unwrap();
+            counts[items.iter().position(|(item, ..)| item == chosen).unwrap()] += 1;
+        }
+        // Effective weights are 3000, 1000 and 3000.
+        let ratio = |a: usize, b: usize| counts[a] as f64 / counts[b] as f64;
+        assert!((2.7..3.3).contains(&ratio(0, 1)), "{counts:?}");
+        assert!((0.9..1.1).contains(&ratio(0, 2)), "{counts:?}");
+
+        let late = [("late", now, 5), ("later", now + 1, 5)];
+        assert_eq!(rng.choose_time_weighted(&late, now), None);
+        assert_eq!(rng.choose_time_weighted(&[late[0], ("early", 0, 1)], now), Some(&"early"));
+    }
+
+    #[test]
+    fn huge_time_weights_do_not_overflow() {
+        let mut rng = test_rng(11);
+        let now = u64::MAX;
+        let items: Vec<(usize, u64, u64)> = (0..40).map(|i| (i, 0, u64::MAX)).collect();
+        let mut counts = [0u32; 40];
+        for _ in 0..4_000 {
+            counts[*rng.choose_time_weighted(&items, now).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&count| (50..150).contains(&count)), "{counts:?}");
+
+        let uneven = [("old", 0, u64::MAX), ("new", now / 2, u64::MAX), ("idle", 0, 0)];
+        let mut counts = [0u32; 3];
+        for _ in 0..6_000 {
+            let chosen = rng.choose_time_weighted(&uneven, now).unwrap();
+            counts[uneven.iter().position(|(item, ..)| item == chosen).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!((1.8..2.2).contains(&ratio), "{counts:?}");
+    }
+
+    #[test]
+    fn random_time_avoids_blackouts() {
+        let mut rng = test_rng(11);
+        let blackouts = [20..30, 25..40, 0..12, 90..200, 50..50];
+        let mut seen = std::collections::BTreeSet::new();
+        for _ in 0..2_000 {
+            let time = rng.random_time_avoiding(10..100, &blackouts).unwrap();
+            assert!((10..100).contains(&time));
+            assert!(blackouts.iter().all(|blackout| !blackout.contains(&time)), "{time}");
+            seen.insert(time);
+        }
+        // 12..20 and 40..90 remain available.
+        assert_eq!(seen.len(), 58);
+        assert_eq!(rng.random_time_avoiding(10..100, &[0..50, 40..120]), None);
+        assert_eq!(rng.random_time_avoiding(10..10, &[]), None);
+        assert_eq!(rng.random_time_avoiding(10..20, &[10..15, 14..19]), Some(19));
+    }
+
+    #[test]
+    fn salted_choice_is_deterministic_and_verifiable() {
+        let items = ["common", "rare", "never"];
+        let weights = [9, 1, 0];
+        let parent = test_rng(7);
+
+        let (choice, proof) = parent.clone().choose_weighted_salted(&items, &weights, b"salt");
+        assert_eq!(
+            (choice, proof),
+            parent.clone().choose_weighted_salted(&items, &weights, b"salt")
+        );
+        let (_, other_proof) = parent.clone().choose_weighted_salted(&items, &weights, b"pepper");
+        assert_ne!(proof, other_proof);
+
+        let index = SecureRng::weighted_index_from_proof(&weights, &proof).unwrap();
+        assert_eq!(choice, Some(&items[index]));
+
+        let mut rng = parent;
+        for _ in 0..200 {
+            let (choice, proof) = rng.choose_weighted_salted(&items, &weights, b"salt");
+            assert_ne!(choice, Some(&"never"));
+            let index = SecureRng::weighted_index_from_proof(&weights, &proof).unwrap();
+            assert_eq!(choice, Some(&items[index]));
+        }
+        assert_eq!(rng.choose_weighted_salted(&items, &[1, 2], b"salt").0, None);
+        assert_eq!(rng.choose_weighted_salted(&items, &[0, 0, 0], b"salt").0, None);
+    }
+
+    #[test]
+    fn luck_is_reciprocal_of_probability() {
+        let mut rng = test_rng(8);
+        let items = ["common", "uncommon", "rare", "never"];
+        let weights = [15, 4, 1, 0];
+        for _ in 0..200 {
+            let (chosen, luck) = rng.choose_weighted_luck(&items, &weights).unwrap();
+            let index = items.iter().position(|item| item == chosen).unwrap();
+            assert_eq!(luck, 1.0 / (weights[index] as f64 / 20.0));
+        }
+        assert_eq!(rng.choose_weighted_luck(&["only"], &[3]), Some((&"only", 1.0)));
+        assert_eq!(rng.choose_weighted_luck(&items, &[1, 2]), None);
+        assert_eq!(rng.choose_weighted_luck(&items, &[0; 4]), None);
+    }
+
+    #[test]
+    fn histogram_without_positive_weights() {
+        assert_eq!(test_rng(2).sample_histogram(&[0, 0], 10), [0, 0]);
+        assert!(test_rng(2).sample_histogram(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn ties_are_broken_uniformly() {
+        let mut rng = test_rng(37);
+        let tied = ["alice", "bob", "carol"];
+        let mut counts = [0u64; 3];
+        for _ in 0..3000 {
+            let winner = rng.break_ties(&tied).unwrap();
+            counts[tied.iter().position(|bidder| bidder == winner).unwrap()] += 1;
+        }
+        assert!(crate::test_utils::chi_square_uniform(&counts, 1000.0) < 13.8, "{counts:?}");
+        assert_eq!(rng.break_ties::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn weighted_ties_favor_earlier_bids() {
+        let mut rng = test_rng(38);
+        let tied = [("late", 1_000), ("early", 997), ("mid", 999)];
+        let mut counts = [0u64; 3];
+        for _ in 0..6000 {
+            let winner = rng.break_ties_weighted(&tied).unwrap();
+            counts[tied.iter().position(|(bidder, _)| bidder == winner).unwrap()] += 1;
+        }
+        // Weights are 1, 4 and 2.
+        assert!(counts[1] > counts[2] && counts[2] > counts[0], "{counts:?}");
+        assert!((3.5..4.5).contains(&(counts[1] as f64 / counts[0] as f64)), "{counts:?}");
+
+        assert_eq!(rng.break_ties_weighted(&[("only", 5)]), Some(&"only"));
+        assert_eq!(rng.break_ties_weighted::<u8>(&[]), None);
+        let extreme = [("first", 0), ("last", u64::MAX)];
+        assert!(rng.break_ties_weighted(&extreme).is_some());
+    }
+
+    #[test]
+    fn huge_u128_weights_do_not_overflow() {
+        let mut rng = test_rng(39);
+        let items = ["a", "b", "c"];
+        let weights = [u128::MAX, u128::MAX, u128::MAX / 2];
+        let mut counts = [0u64; 3];
+        for _ in 0..5000 {
+            let chosen = rng.choose_weighted_u128(&items, &weights).unwrap();
+            counts[items.iter().position(|item| item == chosen).unwrap()] += 1;
+        }
+        // Odds are 2 : 2 : 1.
+        let ratio = |a: usize, b: usize| counts[a] as f64 / counts[b] as f64;
+        assert!((0.9..1.1).contains(&ratio(0, 1)), "{counts:?}");
+        assert!((1.8..2.2).contains(&ratio(0, 2)), "{counts:?}");
+
+        assert_eq!(rng.choose_weighted_u128(&items, &[0, u128::MAX, 0]), Some(&"b"));
+        assert_eq!(rng.choose_weighted_u128(&items, &[0, 0, 0]), None);
+        assert_eq!(rng.choose_weighted_u128(&items, &[1, 2]), None);
+        let all_max = [u128::MAX; 3];
+        assert!(rng.choose_weighted_u128(&items, &all_max).is_some());
+    }
+
+    #[test]
+    fn jitter_stays_below_capped_backoff() {
+        let mut rng = test_rng(40);
+        let max = 60_000_000_000;
+        for attempt in 0..100 {
+            let delay = rng.jitter(1_000_000_000, attempt, max);
+            assert!(delay < max.min(1_000_000_000u64.saturating_mul(1 << attempt.min(63))));
+        }
+        let early: Vec<u64> = (0..200).map(|_| rng.jitter(100, 2, max)).collect();
+        assert!(early.iter().all(|&delay| delay < 400));
+        assert!(early.iter().any(|&delay| delay >= 300));
+
+        // Overflowing ceilings saturate at `max` and still spread over the whole range.
+        let saturated: Vec<u64> = (0..200).map(|_| rng.jitter(u64::MAX, u32::MAX, max)).collect();
+        assert!(saturated.iter().all(|&delay| delay < max));
+        assert!(saturated.iter().any(|&delay| delay > max / 2));
+        assert!(rng.jitter(3, 63, u64::MAX) < u64::MAX);
+
+        assert_eq!(rng.jitter(0, 5, max), 0);
+        assert_eq!(rng.jitter(100, 5, 0), 0);
+    }
+
+    #[test]
+    fn inventory_is_drawn_until_empty() {
+        let mut rng = test_rng(41);
+        let start = HashMap::from([("sword", 3), ("shield", 5), ("potion", 12), ("empty", 0)]);
+        let mut inventory = start.clone();
+        let mut drawn: HashMap<&str, u64> = HashMap::new();
+        while let Some(item) = rng.draw_from_inventory(&mut inventory) {
+            *drawn.entry(item).or_default() += 1;
+        }
+        assert_eq!(inventory, HashMap::from([("empty", 0)]));
+        assert_eq!(drawn.values().sum::<u64>(), 20);
+        assert_eq!(drawn, HashMap::from([("sword", 3), ("shield", 5), ("potion", 12)]));
+
+        let draw_order = |seed| {
+            let mut rng = test_rng(seed);
+            let mut inventory = start.clone();
+            std::iter::from_fn(|| rng.draw_from_inventory(&mut inventory)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw_order(42), draw_order(42));
+    }
+
+    #[test]
+    fn durations_fall_within_bounds() {
+        let mut rng = test_rng(44);
+        let (min, max) = (Duration::from_secs(60), Duration::from_millis(90_500));
+        let delays: Vec<Duration> = (0..500).map(|_| rng.duration(min, max)).collect();
+        assert!(delays.iter().all(|delay| (min..max).contains(delay)));
+        assert!(delays.iter().any(|delay| delay.subsec_nanos() != 0));
+        assert!(delays.iter().any(|&delay| delay > Duration::from_secs(85)));
+
+        let widest = rng.duration(Duration::ZERO, Duration::MAX);
+        assert!(widest < Duration::MAX);
+        assert_eq!(rng.duration(max, min), max);
+        assert_eq!(rng.duration(min, min), min);
+    }
+
+    #[test]
+    fn poll_samples_distinct_voters() {
+        let mut rng = test_rng(43);
+        let voters = 10_000_000;
+        let mut sample = rng.poll_sample(voters, 500);
+        assert_eq!(sample.len(), 500);
+        assert!(sample.iter().all(|&voter| voter < voters));
+        sample.sort_unstable();
+        sample.dedup();
+        assert_eq!(sample.len(), 500);
+
+        let mut everyone = rng.poll_sample(5, 10);
+        everyone.sort_unstable();
+        assert_eq!(everyone, [0, 1, 2, 3, 4]);
+        assert!(rng.poll_sample(0, 3).is_empty());
+        assert!(rng.poll_sample(10, 0).is_empty());
+    }
+}
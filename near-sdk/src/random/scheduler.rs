@@ -0,0 +1,113 @@
+use near_sdk_macros::near;
+
+use super::Rng;
+use crate::env;
+
+/// Smooth weighted round-robin scheduler, as used by nginx for upstream selection.
+///
+/// Each call to [`next`](Self::next) picks the index with the highest current weight. Over a
+/// cycle of `sum(weights)` calls every index is picked exactly `weights[i]` times, and picks of
+/// the same index are spread across the cycle rather than grouped together.
+///
+/// The scheduler is Borsh-serializable, so it can be kept in contract state and continue the
+/// cycle across calls.
+///
+/// # Examples
+///
+/// ```
+/// use near_sdk::random::SmoothWeightedScheduler;
+///
+/// let mut scheduler = SmoothWeightedScheduler::new(&[5, 1, 1]);
+/// let picks: Vec<usize> = (0..7).map(|_| scheduler.next()).collect();
+/// assert_eq!(picks, [0, 0, 1, 0, 2, 0, 0]);
+/// ```
+#[near(inside_nearsdk)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmoothWeightedScheduler {
+    weights: Vec<u64>,
+    current: Vec<i128>,
+}
+
+impl SmoothWeightedScheduler {
+    /// Creates a scheduler over `weights.len()` indices.
+    ///
+    /// Panics if no weight is positive.
+    pub fn new(weights: &[u64]) -> Self {
+        if !weights.iter().any(|&w| w > 0) {
+            env::panic_str("SmoothWeightedScheduler requires at least one positive weight");
+        }
+        Self { weights: weights.to_vec(), current: vec![0; weights.len()] }
+    }
+
+    /// Returns the configured weights.
+    pub fn weights(&self) -> &[u64] {
+        &self.weights
+    }
+
+    /// Returns the next index, breaking ties in favor of the lowest index.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> usize {
+        let max = self.advance();
+        let index = self.current.iter().position(|&c| c == max).unwrap_or_else(|| unreachable!());
+        self.select(index)
+    }
+
+    /// Returns the next index, breaking ties between equally weighted indices uniformly at
+    /// random instead of by position. Selection frequencies over a cycle are unchanged.
+    pub fn randomized_next<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+        let max = self.advance();
+        let tied: Vec<usize> =
+            (0..self.current.len()).filter(|&i| self.current[i] == max).collect();
+        let index = *rng.choice(&tied).unwrap_or_else(|| unreachable!());
+        self.select(index)
+    }
+
+    /// Adds every weight to its current weight and returns the highest current weight.
+    fn advance(&mut self) -> i128 {
+        for (current, &weight) in self.current.iter_mut().zip(&self.weights) {
+            *current += i128::from(weight);
+        }
+        self.current.iter().copied().max().unwrap_or_else(|| unreachable!())
+    }
+
+    fn select(&mut self, index: usize) -> usize {
+        self.current[index] -= self.weights.iter().map(|&w| i128::from(w)).sum::<i128>();
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::test_rng;
+
+    fn counts(weights: &[u64], mut pick: impl FnMut() -> usize) -> Vec<u64> {
+        let cycle: u64 = weights.iter().sum();
+        let mut counts = vec![0; weights.len()];
+        for _ in 0..cycle * 10 {
+            counts[pick()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn frequencies_match_weights() {
+        let weights = [5, 3, 0, 2];
+        let mut scheduler = SmoothWeightedScheduler::new(&weights);
+        assert_eq!(counts(&weights, || scheduler.next()), [50, 30, 0, 20]);
+    }
+
+    #[test]
+    fn randomized_frequencies_match_weights() {
+        let mut rng = test_rng(9);
+        let weights = [2, 2, 2, 1];
+        let mut scheduler = SmoothWeightedScheduler::new(&weights);
+        assert_eq!(counts(&weights, || scheduler.randomized_next(&mut rng)), [20, 20, 20, 10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one positive weight")]
+    fn zero_weights_rejected() {
+        SmoothWeightedScheduler::new(&[0, 0]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{Rng, SecureRng, SecureRngBuilder};
+
+/// Domain tag for the generators derived by [`SecureRng::joint_shuffle`].
+const JOINT_SHUFFLE_DOMAIN: &[u8] = b"near-sdk:random:joint-shuffle:";
+
+impl SecureRng {
+    /// Shuffles `items` among the positions occupied by their class, as given by `class`, so
+    /// that every position keeps an item of the same class while the order within each class
+    /// is randomized.
+    pub fn shuffle_within_classes<T, K: Eq + Hash>(
+        &mut self,
+        items: &mut Vec<T>,
+        class: impl Fn(&T) -> K,
+    ) {
+        // Classes are kept in order of first appearance so the draws do not depend on the
+        // iteration order of the map.
+        let mut class_index = HashMap::new();
+        let mut positions: Vec<Vec<usize>> = Vec::new();
+        for (position, item) in items.iter().enumerate() {
+            let index = *class_index.entry(class(item)).or_insert_with(|| {
+                positions.push(Vec::new());
+                positions.len() - 1
+            });
+            positions[index].push(position);
+        }
+
+        let mut sources: Vec<usize> = (0..items.len()).collect();
+        for class_positions in positions {
+            let mut shuffled = class_positions.clone();
+            self.shuffle(&mut shuffled);
+            for (target, source) in class_positions.into_iter().zip(shuffled) {
+                sources[target] = source;
+            }
+        }
+        let mut slots: Vec<Option<T>> = std::mem::take(items).into_iter().map(Some).collect();
+        items.extend(
+            sources
+                .into_iter()
+                .map(|source| slots[source].take().unwrap_or_else(|| unreachable!())),
+        );
+    }
+
+    /// Returns a uniformly random permutation of `0..n`, for example to assign `n`
+    /// participants to `n` seats.
+    pub fn permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..n).collect();
+        self.shuffle(&mut permutation);
+        permutation
+    }
+
+    /// Shuffles `slice` in place and returns the inverse permutation: the element originally
+    /// at index `i` ends up at index `inverse[i]`.
+    ///
+    /// The original order can be restored later with `original[i] = shuffled[inverse[i]]`.
+    pub fn shuffle_with_inverse<T>(&mut self, slice: &mut [T]) -> Vec<usize> {
+        let mut origin: Vec<usize> = (0..slice.len()).collect();
+        for i in (1..slice.len()).rev() {
+            let j = self.usize(0..=i);
+            slice.swap(i, j);
+            origin.swap(i, j);
+        }
+        let mut inverse = vec![0; slice.len()];
+        for (position, source) in origin.into_iter().enumerate() {
+            inverse[source] = position;
+        }
+        inverse
+    }
+
+    /// Shuffles `slice` so that elements with higher `weights` tend to come first, using the
+    /// Efraimidis-Spirakis algorithm: every element gets a key of `u^(1/w)` for a uniform `u`
+    /// and elements are sorted by descending key.
+    ///
+    /// The first element is chosen with probability proportional to its weight, then the next
+    /// among the remaining ones, and so on. Elements with a weight of zero come last, in
+    /// uniformly random order. Leaves `slice` untouched if `weights` differs in length.
+    pub fn shuffle_weighted<T>(&mut self, slice: &mut [T], weights: &[u64]) {
+        if slice.len() != weights.len() {
+            return;
+        }
+        // `ln(u) / w` orders elements like `u^(1/w)` without underflowing for large weights.
+        let keys: Vec<(bool, f64)> = weights
+            .iter()
+            .map(|&weight| {
+                let log_u = (1.0 - self.f64()).ln();
+                if weight == 0 { (false, log_u) } else { (true, log_u / weight as f64) }
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..slice.len()).collect();
+        order.sort_by(|&a, &b| keys[b].0.cmp(&keys[a].0).then(keys[b].1.total_cmp(&keys[a].1)));
+
+        // Moves the element at `order[i]` to position `i`, following earlier swaps.
+        for i in 0..order.len() {
+            let mut source = order[i];
+            while source < i {
+                source = order[source];
+            }
+            slice.swap(i, source);
+        }
+    }
+
+    /// Shuffles `slice` with a generator derived from the block random seed and both players'
+    /// commitments, so that neither player alone controls the resulting order.
+    ///
+    /// The commitments are hashed in order, so the roles of the two players must be fixed in
+    /// advance. Each commitment has to be submitted before the other is revealed, otherwise
+    /// the last player can grind their commitment against the known one.
+    pub fn joint_shuffle<T>(slice: &mut [T], commit_a: [u8; 32], commit_b: [u8; 32]) {
+        SecureRngBuilder::new()
+            .with_bytes(JOINT_SHUFFLE_DOMAIN)
+            .with_block_seed()
+            .with_bytes(&commit_a)
+            .with_bytes(&commit_b)
+            .build()
+            .shuffle(slice);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::{SecureRng, test_rng};
+    use crate::test_utils::VMContextBuilder;
+
+    #[test]
+    fn classes_keep_their_positions() {
+        let mut rng = test_rng(1);
+        let original: Vec<(char, u32)> = "ababbcaacbbaacab".chars().zip(0..).collect();
+        let mut items = original.clone();
+        rng.shuffle_within_classes(&mut items, |(tier, _)| *tier);
+
+        let tiers = |items: &[(char, u32)]| items.iter().map(|(tier, _)| *tier).collect::<String>();
+        assert_eq!(tiers(&items), tiers(&original));
+        assert_ne!(items, original);
+        let mut sorted = items.clone();
+        sorted.sort_by_key(|(_, id)| *id);
+        assert_eq!(sorted, original);
+
+        let mut single = vec![(0, 'x')];
+        rng.shuffle_within_classes(&mut single, |(class, _)| *class);
+        assert_eq!(single, [(0, 'x')]);
+    }
+
+    #[test]
+    fn permutation_contains_every_index_once() {
+        let mut rng = test_rng(5);
+        for n in [0, 1, 2, 10, 100] {
+            let mut permutation = rng.permutation(n);
+            assert_eq!(permutation.len(), n);
+            permutation.sort_unstable();
+            assert_eq!(permutation, (0..n).collect::<Vec<_>>());
+        }
+
+        let orders = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let mut counts = [0u64; 6];
+        for seed in 0..6_000u32 {
+            let mut seed_bytes = [0; 32];
+            seed_bytes[..4].copy_from_slice(&seed.to_le_bytes());
+            let permutation = SecureRng::from_seed(seed_bytes).permutation(3);
+            counts[orders.iter().position(|order| order[..] == permutation).unwrap()] += 1;
+        }
+        // 5 degrees of freedom: the statistic exceeds 20.5 with probability below 0.1%.
+        let statistic = crate::test_utils::chi_square_uniform(&counts, 1_000.0);
+        assert!(statistic < 20.5, "{statistic} {counts:?}");
+    }
+
+    #[test]
+    fn inverse_restores_original_order() {
+        let mut rng = test_rng(4);
+        let original: Vec<String> = (0..20).map(|i| format!("item-{i}")).collect();
+        let mut shuffled = original.clone();
+        let inverse = rng.shuffle_with_inverse(&mut shuffled);
+        assert_ne!(shuffled, original);
+        for (i, item) in original.iter().enumerate() {
+            assert_eq!(&shuffled[inverse[i]], item);
+        }
+        let restored: Vec<String> = inverse.iter().map(|&i| shuffled[i].clone()).collect();
+        assert_eq!(restored, original);
+
+        assert!(rng.shuffle_with_inverse::<u8>(&mut []).is_empty());
+        assert_eq!(rng.shuffle_with_inverse(&mut [1]), [0]);
+    }
+
+    #[test]
+    fn joint_shuffle_depends_on_both_commitments() {
+        crate::testing_env!(VMContextBuilder::new().random_seed([5; 32]).build());
+        let shuffled = |commit_a, commit_b| {
+            let mut deck: Vec<u32> = (0..52).collect();
+            SecureRng::joint_shuffle(&mut deck, commit_a, commit_b);
+            deck
+        };
+        let deck = shuffled([1; 32], [2; 32]);
+        assert_eq!(deck, shuffled([1; 32], [2; 32]));
+        assert_ne!(deck, (0..52).collect::<Vec<_>>());
+        assert_ne!(deck, shuffled([3; 32], [2; 32]));
+        assert_ne!(deck, shuffled([1; 32], [3; 32]));
+        assert_ne!(deck, shuffled([2; 32], [1; 32]));
+
+        crate::testing_env!(VMContextBuilder::new().random_seed([6; 32]).build());
+        assert_ne!(deck, shuffled([1; 32], [2; 32]));
+    }
+
+    #[test]
+    fn heavier_elements_tend_to_come_first() {
+        let weights = [1, 10, 0, 3, 1];
+        let mut first = [0u32; 5];
+        for seed in 0..=255 {
+            let mut rng = test_rng(seed);
+            for _ in 0..10 {
+                let mut items = [0, 1, 2, 3, 4];
+                rng.shuffle_weighted(&mut items, &weights);
+                let mut sorted = items;
+                sorted.sort_unstable();
+                assert_eq!(sorted, [0, 1, 2, 3, 4]);
+                assert_eq!(items[4], 2);
+                first[items[0]] += 1;
+            }
+        }
+        // The heaviest element leads with probability 10/15.
+        assert!((1_550..1_850).contains(&first[1]), "{first:?}");
+        assert!(first[1] > first[3] && first[3] > first[0] && first[3] > first[4], "{first:?}");
+        assert_eq!(first[2], 0);
+
+        let mut rng = test_rng(0);
+        let mut items = [1, 2, 3];
+        rng.shuffle_weighted(&mut items, &[5, 5]);
+        assert_eq!(items, [1, 2, 3]);
+    }
+}
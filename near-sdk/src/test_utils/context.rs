@@ -189,6 +189,16 @@ impl VMContextBuilder {
         self
     }
 
+    /// Sets the random seed to the little-endian bytes of `n` repeated four times, so that
+    /// statistical tests can loop over seeds such as `0..100`.
+    pub fn random_seed_from_u64(&mut self, n: u64) -> &mut Self {
+        let mut seed = [0; 32];
+        for chunk in seed.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&n.to_le_bytes());
+        }
+        self.random_seed(seed)
+    }
+
     #[cfg(feature = "deterministic-account-ids")]
     pub fn refund_to_account_id(&mut self, beneficiary_id: AccountId) -> &mut Self {
         self.context.refund_to_account_id = beneficiary_id;
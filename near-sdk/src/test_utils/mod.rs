@@ -98,6 +98,22 @@ pub fn get_created_receipts() -> Vec<Receipt> {
     crate::mock::with_mocked_blockchain(|b| b.created_receipts())
 }
 
+/// Returns Pearson's chi-square statistic of the `observed` bucket counts against a uniform
+/// distribution with `expected_per_bucket` draws in every bucket.
+///
+/// Compare the result against the critical value for `observed.len() - 1` degrees of freedom to
+/// decide whether a published draw histogram is plausibly uniform; a fair histogram gives a
+/// statistic close to the number of degrees of freedom.
+pub fn chi_square_uniform(observed: &[u64], expected_per_bucket: f64) -> f64 {
+    observed
+        .iter()
+        .map(|&count| {
+            let delta = count as f64 - expected_per_bucket;
+            delta * delta / expected_per_bucket
+        })
+        .sum()
+}
+
 /// Objects stored on the trie directly should have identifiers. If identifier is not provided
 /// explicitly than `Default` trait would use this index to generate an id.
 #[cfg(test)]
@@ -111,3 +127,30 @@ pub(crate) fn next_trie_id() -> Vec<u8> {
         id.to_le_bytes().to_vec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{VMContextBuilder, chi_square_uniform};
+
+    #[test]
+    fn chi_square_separates_uniform_from_skewed() {
+        let uniform = [98, 103, 101, 97, 100, 99, 102, 100];
+        let uniform_statistic = chi_square_uniform(&uniform, 100.0);
+        assert!(uniform_statistic < 1.0, "{uniform_statistic}");
+
+        let skewed = [160, 40, 100, 100, 100, 100, 100, 100];
+        let skewed_statistic = chi_square_uniform(&skewed, 100.0);
+        assert!(skewed_statistic > 70.0, "{skewed_statistic}");
+
+        assert_eq!(chi_square_uniform(&[5, 5, 5], 5.0), 0.0);
+    }
+
+    #[test]
+    fn random_seed_from_u64_repeats_bytes() {
+        let seed = |n| VMContextBuilder::new().random_seed_from_u64(n).build().random_seed;
+        assert_eq!(seed(7), seed(7));
+        assert_ne!(seed(7), seed(8));
+        assert_eq!(seed(0), [0; 32]);
+        assert_eq!(seed(0x0102), [[2, 1, 0, 0, 0, 0, 0, 0]; 4].concat()[..]);
+    }
+}
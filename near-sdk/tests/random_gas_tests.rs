@@ -0,0 +1,82 @@
+// As wasm VM performance is tested, there is no need to test this on other types of OS.
+// This test runs only on Linux, as it's much slower on OS X due to an interpreted VM.
+#![cfg(target_os = "linux")]
+
+use near_gas::NearGas;
+use near_workspaces::{Account, Contract};
+use serde_json::json;
+
+async fn setup() -> anyhow::Result<(Account, Contract)> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = near_workspaces::compile_project("./tests/test-contracts/random").await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let account = worker.dev_create_account().await?;
+    Ok((account, contract))
+}
+
+async fn gas_burnt(
+    account: &Account,
+    contract: &Contract,
+    method: &str,
+    args: serde_json::Value,
+) -> anyhow::Result<u64> {
+    Ok(account
+        .call(contract.id(), method)
+        .args_json(args)
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .total_gas_burnt
+        .as_gas())
+}
+
+/// Asserts that each of `operations` repetitions of an operation burns less than `max` gas.
+///
+/// `baseline_gas` is the gas burnt by the same call with no repetitions, so the generator
+/// construction and allocations around the operation are not counted.
+///
+/// The bounds are estimates, not measurements: they are set well above the expected cost and
+/// only catch large regressions. Replace them with about twice the printed costs once the
+/// baselines have been measured.
+#[track_caller]
+fn assert_below(name: &str, total_gas: u64, baseline_gas: u64, operations: u64, max: NearGas) {
+    let per_operation = total_gas.saturating_sub(baseline_gas) / operations;
+    println!("{name}: {} per operation", NearGas::from_gas(per_operation));
+    assert!(
+        per_operation < max.as_gas(),
+        "performance regression {name}: {} per operation",
+        NearGas::from_gas(per_operation)
+    );
+}
+
+#[tokio::test]
+async fn random_gas_usage() -> anyhow::Result<()> {
+    let (account, contract) = setup().await?;
+
+    let baseline = gas_burnt(&account, &contract, "construct", json!({ "times": 0 })).await?;
+    let total = gas_burnt(&account, &contract, "construct", json!({ "times": 100 })).await?;
+    assert_below("SecureRng::new", total, baseline, 100, NearGas::from_ggas(40));
+
+    let baseline = gas_burnt(&account, &contract, "draw_u32", json!({ "times": 0 })).await?;
+    let total = gas_burnt(&account, &contract, "draw_u32", json!({ "times": 10_000 })).await?;
+    assert_below("Rng::u32", total, baseline, 10_000, NearGas::from_ggas(1));
+
+    let args = |rounds: u32| json!({ "len": 1000, "rounds": rounds });
+    let baseline = gas_burnt(&account, &contract, "shuffle", args(0)).await?;
+    let total = gas_burnt(&account, &contract, "shuffle", args(10)).await?;
+    assert_below("Rng::shuffle of 1000 elements", total, baseline, 10, NearGas::from_ggas(400));
+
+    let args = |rounds: u32| json!({ "len": 1000, "count": 10, "rounds": rounds });
+    let baseline = gas_burnt(&account, &contract, "sample_multiple", args(0)).await?;
+    let total = gas_burnt(&account, &contract, "sample_multiple", args(10)).await?;
+    assert_below(
+        "Rng::sample_multiple of 10 out of 1000",
+        total,
+        baseline,
+        10,
+        NearGas::from_ggas(400),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use near_sdk::random::{RandomVariant, SecureRng};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+
+#[derive(RandomVariant, Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[derive(RandomVariant, Debug, Clone, Copy, PartialEq, Eq)]
+enum Loot {
+    #[weight(3)]
+    Common,
+    #[weight(1)]
+    Rare,
+    #[weight(0)]
+    Never,
+}
+
+#[derive(RandomVariant, Debug, Clone, Copy, PartialEq, Eq)]
+enum Prize {
+    #[weight(6)]
+    Common,
+    #[weight(3)]
+    Rare,
+    #[weight(1)]
+    Legendary,
+}
+
+#[test]
+fn uniform_variants_all_appear() {
+    testing_env!(VMContextBuilder::new().random_seed([1; 32]).build());
+    let mut rng = SecureRng::new();
+    let mut counts = [0u32; 3];
+    for _ in 0..3_000 {
+        counts[Color::random_variant(&mut rng) as usize] += 1;
+    }
+    assert!(counts.iter().all(|&count| (800..1200).contains(&count)), "{counts:?}");
+}
+
+#[test]
+fn weighted_variants_follow_weights() {
+    testing_env!(VMContextBuilder::new().random_seed([2; 32]).build());
+    let mut rng = SecureRng::new();
+    let mut counts = [0u32; 3];
+    for _ in 0..8_000 {
+        counts[Loot::random_variant(&mut rng) as usize] += 1;
+    }
+    assert_eq!(counts[Loot::Never as usize], 0);
+    let ratio = counts[Loot::Common as usize] as f64 / counts[Loot::Rare as usize] as f64;
+    assert!((2.7..3.3).contains(&ratio), "{counts:?}");
+}
+
+#[test]
+fn three_weighted_variants_match_frequencies() {
+    testing_env!(VMContextBuilder::new().random_seed([3; 32]).build());
+    let mut rng = SecureRng::new();
+    let mut counts = [0u32; 3];
+    for _ in 0..10_000 {
+        counts[Prize::random_variant(&mut rng) as usize] += 1;
+    }
+    for (count, expected) in counts.into_iter().zip([6_000, 3_000, 1_000]) {
+        assert!(count.abs_diff(expected) < expected / 10, "{counts:?}");
+    }
+}
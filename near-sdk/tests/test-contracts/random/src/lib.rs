@@ -0,0 +1,42 @@
+use near_sdk::near;
+use near_sdk::random::{Rng, RngCore, SecureRng};
+
+#[near(contract_state)]
+#[derive(Default)]
+// This contract is designed for measuring the gas used by `near_sdk::random`.
+pub struct RandomContract {}
+
+#[near]
+impl RandomContract {
+    /// Creates `times` generators. The outputs are combined so that they are not optimized out.
+    pub fn construct(&self, times: u32) -> u64 {
+        (0..times).fold(0, |acc, _| acc ^ SecureRng::new().next_u64())
+    }
+
+    /// Draws `times` bounded `u32` values from one generator.
+    pub fn draw_u32(&self, times: u32) -> u32 {
+        let mut rng = SecureRng::new();
+        (0..times).fold(0, |acc, _| acc ^ rng.u32(0..1000))
+    }
+
+    /// Shuffles a slice of `len` elements `rounds` times.
+    pub fn shuffle(&self, len: u32, rounds: u32) -> Option<u32> {
+        let mut items: Vec<u32> = (0..len).collect();
+        let mut rng = SecureRng::new();
+        for _ in 0..rounds {
+            rng.shuffle(&mut items);
+        }
+        items.first().copied()
+    }
+
+    /// Samples `count` elements out of a slice of `len` elements, `rounds` times.
+    pub fn sample_multiple(&self, len: u32, count: u32, rounds: u32) -> u32 {
+        let items: Vec<u32> = (0..len).collect();
+        let mut rng = SecureRng::new();
+        (0..rounds).fold(0, |acc, _| {
+            rng.sample_multiple(&items, count as usize)
+                .into_iter()
+                .fold(acc, |acc, item| acc ^ item)
+        })
+    }
+}